@@ -0,0 +1,291 @@
+//! Synchronous interface for interacting with the OneDrive service
+//!
+//! Wraps the asynchronous [`OneDrive`](crate::api::OneDrive) client for use
+//! by applications that do not otherwise need an async runtime. Each client
+//! manages its own private runtime, which is shared with the entities it
+//! returns, so these methods must not be called from within an async context
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onedrive_manager::api::blocking::OneDrive;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! let service = OneDrive::new("my-access-token")?;
+//! for item in service.list("/Documents")? {
+//!     println!("{}", item.name());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::error::Error;
+use std::fs::File;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use simple_error::SimpleError;
+use tempfile::NamedTempFile;
+use tokio::runtime::{Builder, Runtime};
+
+use super::driveitem::{ConflictBehavior, DriveItem as AsyncDriveItem, ListOptions};
+use super::onedrive::OneDrive as AsyncOneDrive;
+use super::user::User as AsyncUser;
+use crate::paths::split_path;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Blocking client for the OneDrive REST API
+#[derive(Debug)]
+pub struct OneDrive {
+    inner: AsyncOneDrive,
+    runtime: Arc<Runtime>,
+}
+
+impl OneDrive {
+    /// Constructs a new instance of our blocking OneDrive API client
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - API key used to authenticate with.
+    pub fn new(token: &str) -> MyResult<Self> {
+        OneDrive::from_async(AsyncOneDrive::new(token))
+    }
+
+    /// Constructs a blocking client from a preconfigured asynchronous one,
+    /// allowing the options provided by the `OneDriveBuilder` to be used
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Asynchronous client used to perform all operations
+    pub fn from_async(inner: AsyncOneDrive) -> MyResult<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(OneDrive {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Helper method that runs an asynchronous operation to completion
+    fn wait<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Helper method that wraps an item loaded by the asynchronous client
+    fn item_from(&self, inner: AsyncDriveItem) -> DriveItem {
+        DriveItem {
+            inner,
+            runtime: Arc::clone(&self.runtime),
+        }
+    }
+
+    /// Retrieves profile data for the currently logged in user
+    pub fn me(&self) -> MyResult<User> {
+        let inner = self.wait(self.inner.me())?;
+        Ok(User { inner })
+    }
+
+    /// Gets metadata for a file or folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item, ie: "/Documents/Budget.xlsx"
+    pub fn item(&self, path: &str) -> MyResult<DriveItem> {
+        let inner = self.wait(self.inner.item_by_path(path))?;
+        Ok(self.item_from(inner))
+    }
+
+    /// Gets every item contained within a folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the folder to list, ie: "/Documents"
+    pub fn list(&self, path: &str) -> MyResult<Vec<DriveItem>> {
+        self.item(path)?.children()
+    }
+
+    /// Uploads a local file to a folder, failing if the file already exists
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Path to the local file to upload
+    /// * `destination` - Path to the folder to upload the file to
+    pub fn upload(&self, source: &Path, destination: &str) -> MyResult<DriveItem> {
+        let name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| SimpleError::new("Source path does not refer to a file"))?;
+        let file = File::open(source)?;
+        let size = file.metadata()?.len();
+        let inner = self.wait(async {
+            let mut file = tokio::fs::File::from_std(file);
+            let folder = self.inner.item_by_path(destination).await?;
+            folder
+                .upload_from(name, &mut file, size, ConflictBehavior::Fail)
+                .await
+        })?;
+        Ok(self.item_from(inner))
+    }
+
+    /// Downloads a file to the local file system
+    /// Returns the number of bytes downloaded
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Path to the file to download
+    /// * `destination` - Path to the local file to create. Any existing file
+    ///                   is only replaced once the download has completed
+    pub fn download(&self, source: &str, destination: &Path) -> MyResult<u64> {
+        self.item(source)?.download(destination)
+    }
+
+    /// Creates a new folder, failing if an item with the same name exists
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the folder to create, ie: "/Documents/Reports"
+    pub fn mkdir(&self, path: &str) -> MyResult<DriveItem> {
+        let (parent, name) = split_path(path)?;
+        let inner = self.wait(async {
+            let folder = self.inner.item_by_path(parent).await?;
+            folder.create_folder(name, ConflictBehavior::Fail).await
+        })?;
+        Ok(self.item_from(inner))
+    }
+
+    /// Deletes a file or folder, along with all the contents of the folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item to delete
+    pub fn delete(&self, path: &str) -> MyResult<()> {
+        self.item(path)?.delete()
+    }
+}
+
+/// Profile data describing a OneDrive user
+#[derive(Debug)]
+pub struct User {
+    inner: AsyncUser,
+}
+
+impl User {
+    /// Unique identifier of the user
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// Name of the user, as shown to other users
+    pub fn display_name(&self) -> &str {
+        &self.inner.display_name
+    }
+
+    /// Name the user signs in with, usually an email address
+    pub fn user_principal_name(&self) -> &str {
+        &self.inner.user_principal_name
+    }
+
+    /// Email address of the user, if they have one
+    pub fn mail(&self) -> Option<&str> {
+        self.inner.mail.as_deref()
+    }
+}
+
+/// File or folder stored in OneDrive, whose operations block until they
+/// have completed
+#[derive(Debug, Clone)]
+pub struct DriveItem {
+    inner: AsyncDriveItem,
+    runtime: Arc<Runtime>,
+}
+
+impl DriveItem {
+    /// Unique identifier of the item within its drive
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// Name of the item, without the path of its parent folder
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Size of the item in bytes. The size of a folder includes all of its
+    /// contents
+    pub fn size(&self) -> Option<u64> {
+        self.inner.size
+    }
+
+    /// Date and time the item was last modified
+    pub fn last_modified(&self) -> Option<DateTime<Utc>> {
+        self.inner.last_modified_date_time
+    }
+
+    /// Gets the full path to this item, relative to the root of its drive
+    /// ie: "/Documents/Budget.xlsx"
+    pub fn path(&self) -> Option<String> {
+        self.inner.path()
+    }
+
+    /// Returns true if this item represents a file
+    pub fn is_file(&self) -> bool {
+        self.inner.is_file()
+    }
+
+    /// Returns true if this item represents a folder
+    pub fn is_folder(&self) -> bool {
+        self.inner.is_folder()
+    }
+
+    /// Gets every item contained within this folder
+    pub fn children(&self) -> MyResult<Vec<DriveItem>> {
+        let children: Vec<AsyncDriveItem> = self
+            .runtime
+            .block_on(self.inner.children(&ListOptions::default()).try_collect())?;
+        Ok(children
+            .into_iter()
+            .map(|inner| DriveItem {
+                inner,
+                runtime: Arc::clone(&self.runtime),
+            })
+            .collect())
+    }
+
+    /// Downloads the content of this file, writing it to the given output
+    /// Returns the number of bytes written
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Destination for the file content
+    pub fn download_to<W: Write + Send>(&self, writer: &mut W) -> MyResult<u64> {
+        self.runtime.block_on(self.inner.download_to(writer))
+    }
+
+    /// Downloads the content of this file to the local file system
+    /// Returns the number of bytes downloaded
+    ///
+    /// # Arguments
+    ///
+    /// * `destination` - Path to the local file to create. Any existing file
+    ///                   is only replaced once the download has completed
+    pub fn download(&self, destination: &Path) -> MyResult<u64> {
+        // Content is written next to the destination, so a failed download
+        // never leaves a partial file behind in its place
+        let folder = match destination.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let mut temp = NamedTempFile::new_in(folder)?;
+        let size = self.download_to(temp.as_file_mut())?;
+        temp.persist(destination)?;
+        Ok(size)
+    }
+
+    /// Deletes this item from OneDrive. Folders are deleted along with
+    /// all of their contents
+    pub fn delete(&self) -> MyResult<()> {
+        self.runtime.block_on(self.inner.delete())
+    }
+}
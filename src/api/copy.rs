@@ -0,0 +1,144 @@
+//! Primitives for monitoring copy operations performed by OneDrive
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use simple_error::SimpleError;
+
+use super::onedrive::OneDriveApi;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Progress report for a copy operation running on the OneDrive service
+/// See API docs for more details
+///     https://docs.microsoft.com/en-us/graph/long-running-actions-overview
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyStatus {
+    /// Current state of the operation, ie: "inProgress" or "completed"
+    pub status: String,
+    /// Value between 0 and 100 indicating how much of the operation is done
+    #[serde(default)]
+    pub percentage_complete: f64,
+    /// Unique identifier of the copied item, once the operation completes
+    pub resource_id: Option<String>,
+    /// Details describing why the operation failed, if it did
+    pub error: Option<Value>,
+}
+
+impl CopyStatus {
+    /// Returns true if the copy finished successfully
+    pub fn is_complete(&self) -> bool {
+        self.status == "completed"
+    }
+
+    /// Returns true if the copy has stopped without completing
+    pub fn is_failed(&self) -> bool {
+        self.status == "failed"
+    }
+}
+
+/// Copy operation running in the background on the OneDrive service
+#[derive(Debug)]
+pub struct CopyJob {
+    /// Pre-authenticated URL reporting the progress of the operation
+    /// May be queried by any HTTP client until the operation completes
+    pub monitor_url: String,
+    api: Arc<OneDriveApi>,
+}
+
+impl CopyJob {
+    /// Constructs new instances of the CopyJob struct
+    ///
+    /// # Arguments
+    ///
+    /// * `monitor_url` - URL reporting the progress of the operation
+    /// * `api` - Shared reference to the interface used to communicate with
+    ///           the OneDrive REST API
+    pub(crate) fn new(monitor_url: &str, api: Arc<OneDriveApi>) -> CopyJob {
+        CopyJob {
+            monitor_url: monitor_url.to_string(),
+            api,
+        }
+    }
+
+    /// Gets the current progress of the operation
+    pub async fn status(&self) -> MyResult<CopyStatus> {
+        // NOTE: monitor URLs are pre-authenticated, so we must not provide
+        //       our access token with these requests
+        let opt_resp = self
+            .api
+            .client
+            .get(&self.monitor_url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        // Some versions of the service redirect to the copied item once the
+        // operation completes, rather than reporting a final status
+        if opt_resp.status().is_redirection() {
+            return Ok(CopyStatus {
+                status: "completed".to_string(),
+                percentage_complete: 100.0,
+                resource_id: None,
+                error: None,
+            });
+        }
+        Ok(opt_resp.json().await?)
+    }
+
+    /// Polls the progress of the operation until it completes
+    /// Returns the final status of the operation, or an error if it failed
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time to wait between each progress check
+    /// * `progress` - Callback notified of the status of the operation each
+    ///                time it is checked
+    pub async fn wait<F: FnMut(&CopyStatus) + Send>(
+        &self,
+        interval: Duration,
+        mut progress: F,
+    ) -> MyResult<CopyStatus> {
+        loop {
+            let status = self.status().await?;
+            progress(&status);
+            if status.is_complete() {
+                return Ok(status);
+            }
+            if status.is_failed() {
+                let details = status.error.map(|e| e.to_string()).unwrap_or_default();
+                let msg = format!("OneDrive failed to copy the item {}", details);
+                return Err(SimpleError::new(msg.trim_end()).into());
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_copy_status() {
+        let data = r#"{
+            "operation": "itemCopy",
+            "percentageComplete": 27.8,
+            "status": "inProgress"
+        }"#;
+        let status: CopyStatus = serde_json::from_str(data).unwrap();
+        assert_eq!(status.percentage_complete, 27.8);
+        assert!(!status.is_complete());
+        assert!(!status.is_failed());
+
+        let data = r#"{"status": "completed", "resourceId": "ABC123!105"}"#;
+        let status: CopyStatus = serde_json::from_str(data).unwrap();
+        assert!(status.is_complete());
+        assert_eq!(status.resource_id.unwrap(), "ABC123!105");
+    }
+}
@@ -1,47 +1,51 @@
 //! Primitives for manipulating OneDrive drives
 use std::error::Error;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use reqwest::Response;
 use serde::Deserialize;
-use serde_json::Value;
+use simple_error::SimpleError;
 
-use super::driveitem::DriveItemList;
+use super::driveitem::{encode_path, DriveItem, IdentitySet};
 use super::onedrive::OneDriveApi;
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct FileSystemInfo {
-    pub created_date_time: String,
-    pub last_modified_date_time: String,
-}
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
+/// Abstraction around a OneDrive drive, being the top level container
+/// for all files and folders owned by a user
+/// See API docs for more details
+///     https://docs.microsoft.com/en-us/graph/api/resources/drive
 pub struct Drive {
-    #[serde(rename = "@odata.context")]
-    pub context: String,
-    pub c_tag: String,
-    pub created_by: Value,
-    // TODO: parse into structured date/time format
-    pub created_date_time: String,
-    pub e_tag: String,
-    pub file_system_info: FileSystemInfo,
-    pub folder: Value,
     pub id: String,
-    pub last_modified_by: Value,
-    pub last_modified_date_time: String,
-    pub name: String,
-    // TODO: Parse into parent drive/folder object
-    pub parent_reference: Value,
-    pub root: Value,
-    pub size: usize,
-    pub web_url: String,
+    pub drive_type: String,
+    pub name: Option<String>,
+    pub web_url: Option<String>,
+    /// User or group that owns the drive
+    pub owner: Option<IdentitySet>,
+    /// Storage space used by and available to the drive
+    pub quota: Option<Quota>,
     #[serde(skip)]
     url: String,
     #[serde(skip)]
-    api: Option<Rc<OneDriveApi>>,
+    api: Option<Arc<OneDriveApi>>,
+}
+
+/// Storage space used by and available to a drive. All sizes are in bytes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Quota {
+    /// Total storage space allocated to the drive
+    pub total: Option<u64>,
+    /// Storage space used by all files in the drive, including deleted files
+    pub used: Option<u64>,
+    /// Storage space still available in the drive
+    pub remaining: Option<u64>,
+    /// Storage space used by files in the recycle bin
+    pub deleted: Option<u64>,
+    /// How close the drive is to running out of space, being one of
+    /// "normal", "nearing", "critical" or "exceeded"
+    pub state: Option<String>,
 }
 
 impl Drive {
@@ -53,31 +57,86 @@ impl Drive {
     /// * `url` - Full URL to the REST API endpoint managed by this entity
     /// * `api` - Shared reference to the interface used to communicate with
     ///           the OneDrive REST API
-    pub async fn new(resp: Response, url: &str, api: Rc<OneDriveApi>) -> Drive {
-        let mut retval: Drive = resp.json().await.unwrap();
+    pub(crate) async fn new(resp: Response, url: &str, api: Arc<OneDriveApi>) -> MyResult<Drive> {
+        let mut retval: Drive = resp.json().await?;
         retval.url = url.to_string();
         retval.api = Some(api);
-        retval
+        Ok(retval)
     }
 
     /// Helper method that unwraps a reference to the REST API interface used
-    /// by the impl for making dynamic API calls
-    fn api(&self) -> &Rc<OneDriveApi> {
-        self.api.as_ref().unwrap()
+    /// by the impl for making dynamic API calls. Fails for entities which
+    /// were not loaded through a client, such as those deserialized by callers
+    fn api(&self) -> MyResult<&Arc<OneDriveApi>> {
+        self.api
+            .as_ref()
+            .ok_or_else(|| SimpleError::new("Drive is not bound to a client").into())
     }
 
-    /// Gets a list of 0 or more drive items contained within this drive
-    pub async fn children(&self) -> MyResult<DriveItemList> {
-        let url = format!("{}{}", self.url, "/children");
-        // TODO: move all mechanics for REST API into a OneDriveApi
-        let opt_resp = self
-            .api()
-            .client
-            .get(url)
-            .bearer_auth(&self.api().access_token)
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(opt_resp.json().await?)
+    /// Gets the root folder of this drive
+    pub async fn root(&self) -> MyResult<DriveItem> {
+        let url = format!("{}{}", self.url, "/root");
+        let opt_resp = self.api()?.get(&url).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
     }
+
+    /// Gets a file or folder from this drive by its path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item, relative to the root folder of the drive
+    ///            ie: "/Documents/Budget.xlsx". An empty path or "/" refers
+    ///            to the root folder itself
+    pub async fn item_by_path(&self, path: &str) -> MyResult<DriveItem> {
+        get_item_by_path(self.api()?, &self.url, path).await
+    }
+
+    /// Gets a file or folder from this drive by its unique identifier
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Unique identifier of the item within the drive
+    pub async fn item_by_id(&self, id: &str) -> MyResult<DriveItem> {
+        get_item_by_id(self.api()?, &self.url, id).await
+    }
+}
+
+/// Loads a file or folder from a drive by its path
+///
+/// # Arguments
+///
+/// * `api` - Shared reference to the interface used to communicate with
+///           the OneDrive REST API
+/// * `drive_url` - Full URL to the REST API endpoint managing the drive
+/// * `path` - Path to the item, relative to the root folder of the drive
+pub(crate) async fn get_item_by_path(
+    api: &Arc<OneDriveApi>,
+    drive_url: &str,
+    path: &str,
+) -> MyResult<DriveItem> {
+    let encoded = encode_path(path);
+    let url = match encoded.is_empty() {
+        true => format!("{}/root", drive_url),
+        false => format!("{}/root:/{}:", drive_url, encoded),
+    };
+    let opt_resp = api.get(&url).await?;
+    DriveItem::new(opt_resp, Arc::clone(api)).await
+}
+
+/// Loads a file or folder from a drive by its unique identifier
+///
+/// # Arguments
+///
+/// * `api` - Shared reference to the interface used to communicate with
+///           the OneDrive REST API
+/// * `drive_url` - Full URL to the REST API endpoint managing the drive
+/// * `id` - Unique identifier of the item within the drive
+pub(crate) async fn get_item_by_id(
+    api: &Arc<OneDriveApi>,
+    drive_url: &str,
+    id: &str,
+) -> MyResult<DriveItem> {
+    let url = format!("{}/items/{}", drive_url, id);
+    let opt_resp = api.get(&url).await?;
+    DriveItem::new(opt_resp, Arc::clone(api)).await
 }
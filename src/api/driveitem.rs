@@ -1,20 +1,651 @@
 //! Primitives for manipulating OneDrive drive items
 use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use reqwest::header::LOCATION;
+use reqwest::Response;
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use simple_error::SimpleError;
+use tokio::io::AsyncRead;
+use urlencoding::encode;
+
+use super::copy::CopyJob;
+use super::onedrive::OneDriveApi;
+use super::upload::UploadSession;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Lazy-loading stream of drive items which transparently fetches subsequent
+/// pages of results from OneDrive as the stream is consumed
+pub type DriveItemStream = BoxStream<'static, MyResult<DriveItem>>;
 
 /// Abstraction around a single drive item entity from OneDrive
+/// See API docs for more details
+///     https://docs.microsoft.com/en-us/graph/api/resources/driveitem
 #[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DriveItem {
+    pub id: String,
     pub name: String,
+    pub size: Option<u64>,
+    pub e_tag: Option<String>,
+    pub c_tag: Option<String>,
+    pub created_date_time: Option<DateTime<Utc>>,
+    pub last_modified_date_time: Option<DateTime<Utc>>,
+    pub web_url: Option<String>,
+    pub parent_reference: Option<ItemReference>,
+    pub file: Option<File>,
+    pub folder: Option<Folder>,
+    pub file_system_info: Option<FileSystemInfo>,
+    pub created_by: Option<IdentitySet>,
+    pub last_modified_by: Option<IdentitySet>,
+    pub remote_item: Option<RemoteItem>,
+    pub deleted: Option<Deleted>,
+    pub package: Option<Package>,
+    pub root: Option<Root>,
+    pub shared: Option<Shared>,
 
+    /// Properties returned by the API which are not explicitly modelled above
+    /// Preserved so new features of the API are still accessible to callers
     #[serde(flatten)]
     pub extras: HashMap<String, Value>,
+    #[serde(skip)]
+    api: Option<Arc<OneDriveApi>>,
+}
+
+impl DriveItem {
+    /// Constructs new instances of the DriveItem struct
+    ///
+    /// # Arguments
+    ///
+    /// * `resp` - HTTP response data loaded from the OneDrive API
+    /// * `api` - Shared reference to the interface used to communicate with
+    ///           the OneDrive REST API
+    pub(crate) async fn new(resp: Response, api: Arc<OneDriveApi>) -> MyResult<DriveItem> {
+        let mut retval: DriveItem = resp.json().await?;
+        retval.api = Some(api);
+        Ok(retval)
+    }
+
+    /// Helper method that unwraps a reference to the REST API interface used
+    /// by the impl for making dynamic API calls. Fails for entities which
+    /// were not loaded through a client, such as those deserialized by callers
+    fn api(&self) -> MyResult<&Arc<OneDriveApi>> {
+        self.api
+            .as_ref()
+            .ok_or_else(|| SimpleError::new("Item is not bound to a client").into())
+    }
+
+    /// Full URL to the REST API endpoint managed by this entity
+    fn url(&self) -> MyResult<String> {
+        let drive_id = self
+            .parent_reference
+            .as_ref()
+            .and_then(|p| p.drive_id.as_ref());
+        Ok(match drive_id {
+            Some(d) => self.api()?.url(&format!("/drives/{}/items/{}", d, self.id)),
+            None => self.api()?.url(&format!("/me/drive/items/{}", self.id)),
+        })
+    }
+
+    /// Gets a stream of the drive items listed by an endpoint of this entity
+    /// The stream produces a single error if this item is not bound to a client
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the endpoint, relative to the URL of this entity
+    /// * `options` - Paging options that control the size of the results
+    fn list(&self, path: &str, options: &ListOptions) -> DriveItemStream {
+        match self
+            .api()
+            .and_then(|api| Ok((Arc::clone(api), self.url()?)))
+        {
+            Ok((api, url)) => item_stream(api, &format!("{}{}", url, path), options),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        }
+    }
+
+    /// Gets the full path to this item, relative to the root of its drive
+    /// ie: "/Documents/Budget.xlsx"
+    ///
+    /// Returns None if OneDrive did not provide the location of the parent
+    /// folder for the item, as is the case for items shared from other drives
+    pub fn path(&self) -> Option<String> {
+        if self.root.is_some() {
+            return Some("/".to_string());
+        }
+        let parent_path = self.parent_reference.as_ref()?.path.as_ref()?;
+        // Parent paths take the form "/drive/root:/path/to/folder"
+        let (_, parent) = parent_path.split_once("root:")?;
+        Some(format!("{}/{}", parent.trim_end_matches('/'), self.name))
+    }
+
+    /// Loads the latest metadata for this item from OneDrive
+    pub async fn reload(&self) -> MyResult<DriveItem> {
+        let opt_resp = self.api()?.get(&self.url()?).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
+    }
+
+    /// Gets a stream of 0 or more drive items contained within this folder
+    /// Additional pages of results are loaded lazily as the stream is consumed
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Paging options that control the size of the results
+    pub fn children(&self, options: &ListOptions) -> DriveItemStream {
+        self.list("/children", options)
+    }
+
+    /// Gets a file or folder contained within this folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item to load, relative to this folder
+    ///            ie: "Documents/Budget.xlsx"
+    pub async fn child(&self, path: &str) -> MyResult<DriveItem> {
+        let encoded = encode_path(path);
+        if encoded.is_empty() {
+            return self.reload().await;
+        }
+        let url = format!("{}:/{}:", self.url()?, encoded);
+        let opt_resp = self.api()?.get(&url).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
+    }
+
+    /// Searches this folder and all of its subfolders for items matching a
+    /// query. OneDrive matches the query against the names, metadata and
+    /// content of files, and returns the most relevant items first
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Text to search for
+    /// * `options` - Paging options that control the size of the results
+    pub fn search(&self, query: &str, options: &ListOptions) -> DriveItemStream {
+        // Single quotes delimit the query, so any within it must be doubled
+        let escaped = encode(&query.replace('\'', "''")).into_owned();
+        self.list(&format!("/search(q='{}')", escaped), options)
+    }
+
+    /// Gets the folder containing this item
+    /// Returns None if this item is the root folder of its drive
+    pub async fn parent(&self) -> MyResult<Option<DriveItem>> {
+        let parent_id = match self.parent_reference.as_ref().and_then(|p| p.id.as_ref()) {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let url = match self
+            .parent_reference
+            .as_ref()
+            .and_then(|p| p.drive_id.as_ref())
+        {
+            Some(d) => self
+                .api()?
+                .url(&format!("/drives/{}/items/{}", d, parent_id)),
+            None => self.api()?.url(&format!("/me/drive/items/{}", parent_id)),
+        };
+        let opt_resp = self.api()?.get(&url).await?;
+        Ok(Some(
+            DriveItem::new(opt_resp, Arc::clone(self.api()?)).await?,
+        ))
+    }
+
+    /// Creates a new folder within this folder
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the folder to create
+    /// * `conflict` - How to handle the case where an item with the same
+    ///                name already exists
+    pub async fn create_folder(
+        &self,
+        name: &str,
+        conflict: ConflictBehavior,
+    ) -> MyResult<DriveItem> {
+        let url = format!("{}{}", self.url()?, "/children");
+        let body = json!({
+            "name": name,
+            "folder": {},
+            "@microsoft.graph.conflictBehavior": conflict.as_str(),
+        });
+        let opt_resp = self.api()?.post(&url, &body).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
+    }
+
+    /// Moves this item to another folder, optionally renaming it
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - Folder to move the item to. May be the current parent
+    ///              of the item to rename it in place
+    /// * `name` - New name for the item
+    /// * `conflict` - How to handle an existing item with the same name in
+    ///                the destination folder
+    pub async fn move_to(
+        &self,
+        parent: &DriveItem,
+        name: &str,
+        conflict: ConflictBehavior,
+    ) -> MyResult<DriveItem> {
+        let url = format!(
+            "{}?@microsoft.graph.conflictBehavior={}",
+            self.url()?,
+            conflict.as_str()
+        );
+        let body = json!({
+            "parentReference": { "id": parent.id },
+            "name": name,
+        });
+        let opt_resp = self.api()?.patch(&url, &body).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
+    }
+
+    /// Starts copying this item to another folder. Folders are copied along
+    /// with all of their contents. The copy is performed in the background
+    /// by OneDrive, and its progress may be tracked with the returned job
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - Folder to copy the item to
+    /// * `name` - Name for the new copy of the item
+    /// * `conflict` - How to handle an existing item with the same name in
+    ///                the destination folder
+    pub async fn copy_to(
+        &self,
+        parent: &DriveItem,
+        name: &str,
+        conflict: ConflictBehavior,
+    ) -> MyResult<CopyJob> {
+        let url = format!(
+            "{}/copy?@microsoft.graph.conflictBehavior={}",
+            self.url()?,
+            conflict.as_str()
+        );
+        // Copying between drives requires the ID of the destination drive
+        let mut parent_reference = json!({ "id": parent.id });
+        if let Some(drive_id) = parent
+            .parent_reference
+            .as_ref()
+            .and_then(|r| r.drive_id.as_ref())
+        {
+            parent_reference["driveId"] = json!(drive_id);
+        }
+        let body = json!({
+            "parentReference": parent_reference,
+            "name": name,
+        });
+        let opt_resp = self.api()?.post(&url, &body).await?;
+        let monitor_url = opt_resp
+            .headers()
+            .get(LOCATION)
+            .ok_or_else(|| SimpleError::new("OneDrive did not provide a monitor URL"))?
+            .to_str()?;
+        Ok(CopyJob::new(monitor_url, Arc::clone(self.api()?)))
+    }
+
+    /// Deletes this item from OneDrive. Folders are deleted along with
+    /// all of their contents
+    pub async fn delete(&self) -> MyResult<()> {
+        self.api()?.delete(&self.url()?).await?;
+        Ok(())
+    }
+
+    /// Deletes this item without moving it to the recycle bin, so it can
+    /// not be restored. Only supported by OneDrive for Business and
+    /// SharePoint drives
+    pub async fn permanent_delete(&self) -> MyResult<()> {
+        let url = format!("{}/permanentDelete", self.url()?);
+        self.api()?.post(&url, &json!({})).await?;
+        Ok(())
+    }
+
+    /// Starts a new resumable upload session for a file stored within this
+    /// folder. Content for the file is transferred using the session
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the file to create within this folder
+    /// * `conflict` - How to handle the case where the file already exists
+    pub async fn create_upload_session(
+        &self,
+        name: &str,
+        conflict: ConflictBehavior,
+    ) -> MyResult<UploadSession> {
+        let url = format!(
+            "{}:/{}:/createUploadSession",
+            self.url()?,
+            encode_path(name)
+        );
+        let body = json!({
+            "item": {
+                "@microsoft.graph.conflictBehavior": conflict.as_str(),
+            }
+        });
+        let opt_resp = self.api()?.post(&url, &body).await?;
+        UploadSession::new(opt_resp, Arc::clone(self.api()?)).await
+    }
+
+    /// Uploads a small file to this folder in a single request
+    /// Files larger than 4MB must be transferred using an upload session
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the file to create within this folder
+    /// * `data` - Content of the file
+    /// * `conflict` - How to handle the case where the file already exists
+    pub async fn upload(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+        conflict: ConflictBehavior,
+    ) -> MyResult<DriveItem> {
+        let url = format!(
+            "{}:/{}:/content?@microsoft.graph.conflictBehavior={}",
+            self.url()?,
+            encode_path(name),
+            conflict.as_str()
+        );
+        let opt_resp = self.api()?.put(&url, data).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
+    }
+
+    /// Uploads a file of any size to this folder, choosing the most
+    /// appropriate transfer mechanism for the amount of data involved
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the file to create within this folder
+    /// * `reader` - Source of the file content
+    /// * `file_size` - Total number of bytes to read from the source
+    /// * `conflict` - How to handle the case where the file already exists
+    pub async fn upload_from<R: AsyncRead + Send + Unpin + ?Sized>(
+        &self,
+        name: &str,
+        reader: &mut R,
+        file_size: u64,
+        conflict: ConflictBehavior,
+    ) -> MyResult<DriveItem> {
+        if file_size == 0 {
+            // Upload sessions can not be used to transfer empty files
+            return self.upload(name, Vec::new(), conflict).await;
+        }
+        let session = self.create_upload_session(name, conflict).await?;
+        session.upload_from(reader, file_size).await
+    }
+
+    /// Opens a stream over the content of this file
+    /// The content can be consumed incrementally using the `chunk` method
+    /// of the response, avoiding loading large files into memory
+    pub async fn download(&self) -> MyResult<Response> {
+        let url = format!("{}/content", self.url()?);
+        self.api()?.get_content(&url, None).await
+    }
+
+    /// Opens a stream over part of the content of this file
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Portion of the file content to download
+    pub async fn download_range(&self, range: ByteRange) -> MyResult<Response> {
+        let url = format!("{}/content", self.url()?);
+        self.api()?.get_content(&url, Some(range)).await
+    }
+
+    /// Downloads the content of this file, writing it to the given output
+    /// Returns the number of bytes written
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Destination for the file content
+    pub async fn download_to<W: Write + Send>(&self, writer: &mut W) -> MyResult<u64> {
+        let mut opt_resp = self.download().await?;
+        let mut total = 0;
+        while let Some(chunk) = opt_resp.chunk().await? {
+            writer.write_all(&chunk)?;
+            total += chunk.len() as u64;
+        }
+        Ok(total)
+    }
+
+    /// Returns true if this item represents a folder
+    pub fn is_folder(&self) -> bool {
+        self.folder.is_some()
+    }
+
+    /// Returns true if this item represents a file
+    pub fn is_file(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Returns true if this item is the root folder of its drive
+    pub fn is_root(&self) -> bool {
+        self.root.is_some()
+    }
+}
+
+/// Reference to a drive item by its location within a drive
+/// Used primarily to describe the parent of a drive item
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemReference {
+    pub drive_id: Option<String>,
+    pub drive_type: Option<String>,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    /// Path to the item, in the form "/drive/root:/path/to/folder"
+    pub path: Option<String>,
+}
+
+/// Metadata describing a drive item which is a file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct File {
+    pub mime_type: Option<String>,
+    pub hashes: Option<Hashes>,
+}
+
+/// Hashes of the content of a file
+/// Which hashes are provided depends on the type of the drive containing
+/// the file (ie: personal vs business accounts)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hashes {
+    pub quick_xor_hash: Option<String>,
+    pub sha1_hash: Option<String>,
+    pub sha256_hash: Option<String>,
+    pub crc32_hash: Option<String>,
+}
+
+/// Metadata describing a drive item which is a folder
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Folder {
+    /// Number of items contained directly within the folder
+    pub child_count: Option<u64>,
+}
+
+/// Timestamps reported by the file system of the client that created
+/// the drive item
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSystemInfo {
+    pub created_date_time: Option<DateTime<Utc>>,
+    pub last_modified_date_time: Option<DateTime<Utc>>,
+}
+
+/// Collection of identities associated with an action performed on an item
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentitySet {
+    pub user: Option<Identity>,
+    pub application: Option<Identity>,
+    pub device: Option<Identity>,
+}
+
+/// Single identity, such as a user or an application
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub id: Option<String>,
+    pub display_name: Option<String>,
 }
 
-/// List of OneDrive drive item entities providing a lazy-loading iterator interface
-/// to simplify interactions with the API
+/// Metadata describing an item stored in another drive, such as an item
+/// shared with the current user
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteItem {
+    pub id: String,
+    pub name: Option<String>,
+    pub size: Option<u64>,
+    pub web_url: Option<String>,
+    pub parent_reference: Option<ItemReference>,
+    pub file: Option<File>,
+    pub folder: Option<Folder>,
+    pub file_system_info: Option<FileSystemInfo>,
+    pub created_by: Option<IdentitySet>,
+    pub last_modified_by: Option<IdentitySet>,
+}
+
+/// Metadata describing a drive item which has been shared with others
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shared {
+    /// Identity of the owner of the shared item
+    pub owner: Option<IdentitySet>,
+    /// Who the item is shared with, being one of "anonymous", "organization"
+    /// or "users"
+    pub scope: Option<String>,
+    /// Identity of the user who shared the item
+    pub shared_by: Option<IdentitySet>,
+    pub shared_date_time: Option<DateTime<Utc>>,
+}
+
+/// Marker indicating a drive item has been deleted
+#[derive(Debug, Clone, Deserialize)]
+pub struct Deleted {
+    pub state: Option<String>,
+}
+
+/// Marker indicating a drive item should be treated as a single unit, such
+/// as a OneNote notebook, rather than as a regular folder
+#[derive(Debug, Clone, Deserialize)]
+pub struct Package {
+    #[serde(rename = "type")]
+    pub package_type: String,
+}
+
+/// Marker indicating a drive item is the root folder of its drive
+#[derive(Debug, Clone, Deserialize)]
+pub struct Root {}
+
+/// Behaviour to apply when creating an item whose name collides with an
+/// existing item in the same folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictBehavior {
+    /// Abort the operation with an error
+    Fail,
+    /// Overwrite the existing item
+    Replace,
+    /// Generate a new, unique name for the item being created
+    Rename,
+}
+
+impl ConflictBehavior {
+    /// Gets the value used to represent this behaviour in the REST API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictBehavior::Fail => "fail",
+            ConflictBehavior::Replace => "replace",
+            ConflictBehavior::Rename => "rename",
+        }
+    }
+}
+
+impl FromStr for ConflictBehavior {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail" => Ok(ConflictBehavior::Fail),
+            "replace" => Ok(ConflictBehavior::Replace),
+            "rename" => Ok(ConflictBehavior::Rename),
+            _ => Err(SimpleError::new(format!(
+                "Unsupported conflict behavior {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Portion of the content of a file to download, in the form of the byte
+/// ranges supported by the HTTP Range header. Offsets are zero based
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// Bytes from the first offset up to and including the second offset
+    Bounded(u64, u64),
+    /// All bytes from the offset up to the end of the file
+    From(u64),
+    /// The given number of bytes at the end of the file
+    Last(u64),
+}
+
+impl ByteRange {
+    /// Gets the value of the HTTP Range header requesting this range
+    pub fn header(&self) -> String {
+        match self {
+            ByteRange::Bounded(start, end) => format!("bytes={}-{}", start, end),
+            ByteRange::From(start) => format!("bytes={}-", start),
+            ByteRange::Last(count) => format!("bytes=-{}", count),
+        }
+    }
+}
+
+impl FromStr for ByteRange {
+    type Err = SimpleError;
+
+    /// Parses ranges of the form "start-end", "start-" or "-count", as
+    /// used by the HTTP Range header
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || SimpleError::new(format!("Invalid range {}", s));
+        let (start, end) = s.split_once('-').ok_or_else(err)?;
+        let parse = |v: &str| match v.starts_with('+') {
+            true => Err(err()),
+            false => v.parse::<u64>().map_err(|_| err()),
+        };
+        match (start, end) {
+            ("", "") => Err(err()),
+            ("", count) => Ok(ByteRange::Last(parse(count)?)),
+            (start, "") => Ok(ByteRange::From(parse(start)?)),
+            (start, end) => match (parse(start)?, parse(end)?) {
+                (start, end) if start <= end => Ok(ByteRange::Bounded(start, end)),
+                _ => Err(err()),
+            },
+        }
+    }
+}
+
+/// Percent-encodes each segment of a path to a drive item so it can be
+/// embedded in a REST API URL. Leading and trailing separators are removed
+///
+/// # Arguments
+///
+/// * `path` - Path to encode, ie: "/My Documents/Budget.xlsx"
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| encode(s).into_owned())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// Single page of OneDrive drive item entities, as returned by any API
+/// endpoint that produces a collection of items
 #[derive(Debug, Deserialize)]
 pub struct DriveItemList {
     #[serde(rename = "value")]
@@ -23,20 +654,250 @@ pub struct DriveItemList {
     pub next_url: Option<String>,
     #[serde(rename = "@odata.deltaLink")]
     pub delta_url: Option<String>,
-    #[serde(skip)]
-    cur_index: usize,
 }
 
-impl Iterator for DriveItemList {
-    type Item = DriveItem;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.cur_index == self.data.len() {
-            return None;
+/// Options that customize how collections of drive items are loaded
+#[derive(Debug, Default, Clone)]
+pub struct ListOptions {
+    /// Number of items to request from OneDrive per page of results
+    /// If not provided the server side default is used (currently 200)
+    pub page_size: Option<usize>,
+    /// Maximum number of items to return across all pages of results
+    /// If not provided, all items in the collection are returned
+    pub limit: Option<usize>,
+}
+
+impl ListOptions {
+    /// Generates the URL for the first page of results for a collection
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint that produces the collection
+    fn first_page_url(&self, url: &str) -> String {
+        match self.page_size {
+            Some(size) => {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                format!("{}{}$top={}", url, separator, size)
+            }
+            None => url.to_string(),
+        }
+    }
+}
+
+/// Constructs a stream over every drive item in a collection, following the
+/// "@odata.nextLink" references provided by OneDrive to load further pages
+/// of results only when the previous page has been consumed
+///
+/// # Arguments
+///
+/// * `api` - Shared reference to the interface used to communicate with
+///           the OneDrive REST API
+/// * `url` - Full URL to the REST API endpoint that produces the collection
+/// * `options` - Paging options that control the size of the results
+pub(crate) fn item_stream(
+    api: Arc<OneDriveApi>,
+    url: &str,
+    options: &ListOptions,
+) -> DriveItemStream {
+    let pages = stream::try_unfold(Some(options.first_page_url(url)), move |next_url| {
+        let api = Arc::clone(&api);
+        async move {
+            let url = match next_url {
+                Some(u) => u,
+                None => return MyResult::Ok(None),
+            };
+            let mut page: DriveItemList = api.get(&url).await?.json().await?;
+            for item in page.data.iter_mut() {
+                item.api = Some(Arc::clone(&api));
+            }
+            Ok(Some((page.data, page.next_url)))
+        }
+    });
+    let items = pages
+        .map_ok(|data| stream::iter(data.into_iter().map(Ok)))
+        .try_flatten();
+
+    match options.limit {
+        Some(limit) => items.take(limit).boxed(),
+        None => items.boxed(),
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::fs::read_to_string;
+    use std::path::PathBuf;
+
+    #[test]
+    fn first_page_url_default() {
+        let options = ListOptions::default();
+        let url = "https://localhost/me/drive/root/children";
+        assert_eq!(options.first_page_url(url), url);
+    }
+
+    #[test]
+    fn first_page_url_with_page_size() {
+        let options = ListOptions {
+            page_size: Some(50),
+            limit: None,
+        };
+        let url = "https://localhost/me/drive/root/children";
+        assert_eq!(
+            options.first_page_url(url),
+            "https://localhost/me/drive/root/children?$top=50"
+        );
+        assert_eq!(
+            options.first_page_url("https://localhost/search?q=x"),
+            "https://localhost/search?q=x&$top=50"
+        );
+    }
+
+    #[test]
+    fn parse_byte_ranges() {
+        let range: ByteRange = "0-99".parse().unwrap();
+        assert_eq!(range, ByteRange::Bounded(0, 99));
+        assert_eq!(range.header(), "bytes=0-99");
+        let range: ByteRange = "100-".parse().unwrap();
+        assert_eq!(range.header(), "bytes=100-");
+        let range: ByteRange = "-500".parse().unwrap();
+        assert_eq!(range, ByteRange::Last(500));
+        assert_eq!(range.header(), "bytes=-500");
+
+        for invalid in ["", "-", "10", "9-1", "a-b", "1-+2", "1-2-3"] {
+            assert!(invalid.parse::<ByteRange>().is_err(), "{}", invalid);
         }
-        // TODO: figure out if we should return a borrowed reference or what
-        // TODO: replace implementation with a shared iter of the 'data' vec
-        let retval = self.data[self.cur_index].clone();
-        self.cur_index += 1;
-        Some(retval)
+    }
+
+    /// Loads the contents of a sample API response from our test data folder
+    fn load_test_data(file_name: &str) -> String {
+        let path: PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "src",
+            "test_data",
+            "drive_items",
+            file_name,
+        ]
+        .iter()
+        .collect();
+        read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn parse_file_item() {
+        let item: DriveItem = serde_json::from_str(&load_test_data("file.json")).unwrap();
+        assert_eq!(item.id, "01BYE5RZ5MYLM2SMX75ZBIPQZIHT6OAYPB");
+        assert_eq!(item.name, "Budget.xlsx");
+        assert_eq!(item.size, Some(35212));
+        assert!(item.is_file());
+        assert!(!item.is_folder());
+
+        let file = item.file.unwrap();
+        assert_eq!(
+            file.mime_type.as_deref(),
+            Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        );
+        let hashes = file.hashes.unwrap();
+        assert_eq!(
+            hashes.quick_xor_hash.as_deref(),
+            Some("wHBcEeDoufN0hQ5nWmcMUw4gbNM=")
+        );
+        assert!(hashes.sha256_hash.is_none());
+
+        let expected_created = Utc.with_ymd_and_hms(2017, 8, 7, 16, 16, 30).unwrap();
+        let expected_modified = Utc.with_ymd_and_hms(2017, 8, 10, 1, 42, 24).unwrap();
+        assert_eq!(item.created_date_time, Some(expected_created));
+        assert_eq!(item.last_modified_date_time, Some(expected_modified));
+        let fs_info = item.file_system_info.unwrap();
+        assert_eq!(fs_info.created_date_time, Some(expected_created));
+        assert_eq!(fs_info.last_modified_date_time, Some(expected_modified));
+
+        let parent = item.parent_reference.unwrap();
+        assert_eq!(parent.path.as_deref(), Some("/drive/root:/Documents"));
+        let user = item.last_modified_by.unwrap().user.unwrap();
+        assert_eq!(user.display_name.as_deref(), Some("Megan Bowen"));
+        let shared = item.shared.unwrap();
+        assert_eq!(shared.scope.as_deref(), Some("users"));
+        assert!(shared.owner.is_none());
+
+        // Properties we don't explicitly model should still be preserved
+        assert!(item.extras.contains_key("@microsoft.graph.downloadUrl"));
+        assert!(!item.extras.contains_key("name"));
+    }
+
+    #[test]
+    fn parse_folder_item() {
+        let item: DriveItem = serde_json::from_str(&load_test_data("folder.json")).unwrap();
+        assert!(item.is_folder());
+        assert!(!item.is_file());
+        assert_eq!(item.folder.unwrap().child_count, Some(3));
+        assert_eq!(item.package.unwrap().package_type, "oneNote");
+        assert!(item.root.is_none());
+        assert!(item.deleted.is_none());
+
+        // Fractional seconds should be preserved when parsing timestamps
+        let created = item.created_date_time.unwrap();
+        assert_eq!(created.timestamp_subsec_millis(), 563);
+        assert!(created < item.last_modified_date_time.unwrap());
+    }
+
+    #[test]
+    fn item_paths() {
+        let item: DriveItem = serde_json::from_str(&load_test_data("file.json")).unwrap();
+        assert_eq!(item.path().as_deref(), Some("/Documents/Budget.xlsx"));
+
+        // Items stored directly in the root folder have a parent path of "/drive/root:"
+        let item: DriveItem = serde_json::from_str(&load_test_data("folder.json")).unwrap();
+        assert_eq!(item.path().as_deref(), Some("/Notebooks"));
+
+        let item: DriveItem =
+            serde_json::from_str(r#"{"id": "1", "name": "root", "root": {}}"#).unwrap();
+        assert_eq!(item.path().as_deref(), Some("/"));
+
+        let item: DriveItem = serde_json::from_str(r#"{"id": "1", "name": "a.txt"}"#).unwrap();
+        assert!(item.path().is_none());
+    }
+
+    #[test]
+    fn encode_item_paths() {
+        assert_eq!(encode_path("/"), "");
+        assert_eq!(encode_path(""), "");
+        assert_eq!(encode_path("/Documents/"), "Documents");
+        assert_eq!(
+            encode_path("/My Documents/100% done#1.txt"),
+            "My%20Documents/100%25%20done%231.txt"
+        );
+    }
+
+    #[test]
+    fn parse_conflict_behavior() {
+        for behavior in [
+            ConflictBehavior::Fail,
+            ConflictBehavior::Replace,
+            ConflictBehavior::Rename,
+        ] {
+            assert_eq!(
+                behavior.as_str().parse::<ConflictBehavior>().unwrap(),
+                behavior
+            );
+        }
+        assert!("overwrite".parse::<ConflictBehavior>().is_err());
+    }
+
+    #[test]
+    fn parse_paged_list() {
+        let data = r#"{
+            "value": [{"id": "1", "name": "a.txt"}, {"id": "2", "name": "b.txt", "size": 12}],
+            "@odata.nextLink": "https://localhost/next"
+        }"#;
+        let page: DriveItemList = serde_json::from_str(data).unwrap();
+        assert_eq!(page.data.len(), 2);
+        assert_eq!(page.data[1].name, "b.txt");
+        assert_eq!(page.next_url.as_deref(), Some("https://localhost/next"));
+        assert!(page.delta_url.is_none());
     }
 }
@@ -1,5 +1,19 @@
 //! Primitives for interacting with the OneDrive service
-pub(crate) mod drive;
-pub(crate) mod driveitem;
-pub(crate) mod onedrive;
-pub(crate) mod user;
+//!
+//! All interactions begin with a [`OneDrive`] client, from which the
+//! profile of the current user, their drives and the files and folders
+//! stored within them can be navigated
+pub mod blocking;
+pub mod copy;
+pub mod drive;
+pub mod driveitem;
+pub mod onedrive;
+pub mod upload;
+pub mod user;
+
+pub use copy::{CopyJob, CopyStatus};
+pub use drive::{Drive, Quota};
+pub use driveitem::{ByteRange, ConflictBehavior, DriveItem, DriveItemStream, ListOptions};
+pub use onedrive::{is_not_found, OneDrive, OneDriveBuilder, DEFAULT_API_URL};
+pub use upload::UploadSession;
+pub use user::User;
@@ -1,18 +1,250 @@
 //! Primary entry point for the module
 //! Defines the basic connection and authentication interface for OneDrive
 
+use crate::api::drive::{get_item_by_id, get_item_by_path, Drive};
+use crate::api::driveitem::{ByteRange, DriveItem};
 use crate::api::user::User;
-use reqwest::Client;
-use std::{error::Error, fmt::Debug, rc::Rc};
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use reqwest::header::{LOCATION, RANGE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde_json::Value;
+use simple_error::SimpleError;
+use std::{error::Error, fmt::Debug, sync::Arc};
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
-const ONEDRIVE_API_URL: &str = "https://graph.microsoft.com/v1.0";
+/// Base URL of the Microsoft Graph REST API used to access OneDrive
+pub const DEFAULT_API_URL: &str = "https://graph.microsoft.com/v1.0";
+
+/// Checks whether an error was caused by a request for an entity that
+/// does not exist, such as a drive item with an unknown path
+///
+/// # Arguments
+///
+/// * `error` - Error produced by any operation in the api module
+pub fn is_not_found(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) => e.status() == Some(StatusCode::NOT_FOUND),
+        None => false,
+    }
+}
 
 /// Abstraction around the low level mechanics of the OneDrive REST API
 #[derive(Debug)]
-pub struct OneDriveApi {
+pub(crate) struct OneDriveApi {
     pub client: Client,
     pub access_token: String,
+    pub base_url: String,
+}
+
+impl OneDriveApi {
+    /// Generates the full URL to a REST API endpoint
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the endpoint, relative to the root of the API
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Sends an authenticated GET request to the OneDrive REST API
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint to query
+    pub(crate) async fn get(&self, url: &str) -> MyResult<Response> {
+        Ok(self
+            .client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Sends an authenticated POST request with a JSON payload to the
+    /// OneDrive REST API
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint to send the data to
+    /// * `body` - JSON payload for the request
+    pub(crate) async fn post(&self, url: &str, body: &Value) -> MyResult<Response> {
+        Ok(self
+            .client
+            .post(url)
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Sends an authenticated PATCH request with a JSON payload to the
+    /// OneDrive REST API
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint managing the entity to update
+    /// * `body` - JSON payload describing the properties to update
+    pub(crate) async fn patch(&self, url: &str, body: &Value) -> MyResult<Response> {
+        Ok(self
+            .client
+            .patch(url)
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Sends an authenticated PUT request with a binary payload to the
+    /// OneDrive REST API
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint to send the data to
+    /// * `data` - Raw content to send as the body of the request
+    pub(crate) async fn put(&self, url: &str, data: Vec<u8>) -> MyResult<Response> {
+        Ok(self
+            .client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .body(data)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Sends an authenticated DELETE request to the OneDrive REST API
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint managing the entity to delete
+    pub(crate) async fn delete(&self, url: &str) -> MyResult<Response> {
+        Ok(self
+            .client
+            .delete(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Sends an authenticated GET request for the content of a file
+    ///
+    /// OneDrive responds to such requests with a redirect to a temporary,
+    /// pre-authenticated download URL, which is followed here without
+    /// sharing our access token with the download service
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Full URL to the REST API endpoint producing the content
+    /// * `range` - Portion of the content to request, or None to request
+    ///             all of it
+    pub(crate) async fn get_content(
+        &self,
+        url: &str,
+        range: Option<ByteRange>,
+    ) -> MyResult<Response> {
+        // The range must be requested from both services, since the
+        // download service does not know which range was originally asked for
+        let with_range = |request: RequestBuilder| match range {
+            Some(r) => request.header(RANGE, r.header()),
+            None => request,
+        };
+        let opt_resp = with_range(self.client.get(url).bearer_auth(&self.access_token))
+            .send()
+            .await?
+            .error_for_status()?;
+        if !opt_resp.status().is_redirection() {
+            return Ok(opt_resp);
+        }
+        let location = opt_resp
+            .headers()
+            .get(LOCATION)
+            .ok_or_else(|| SimpleError::new("OneDrive did not provide a download URL"))?
+            .to_str()?;
+        Ok(with_range(self.client.get(location))
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+}
+
+/// Helper used to configure and construct instances of the OneDrive client
+///
+/// # Example
+///
+/// ```
+/// use onedrive_manager::api::OneDrive;
+///
+/// let service = OneDrive::builder("my-access-token")
+///     .base_url("http://127.0.0.1:8000/v1.0")
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct OneDriveBuilder {
+    access_token: String,
+    base_url: String,
+    client: Option<Client>,
+}
+
+impl OneDriveBuilder {
+    /// Constructs a new builder with the default options
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - API key used to authenticate with.
+    pub fn new(token: &str) -> Self {
+        OneDriveBuilder {
+            access_token: token.to_string(),
+            base_url: DEFAULT_API_URL.to_string(),
+            client: None,
+        }
+    }
+
+    /// Connects the client to an alternative implementation of the REST API,
+    /// such as a mock server used for testing
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL to the root of the REST API
+    ///           ie: "https://graph.microsoft.com/v1.0"
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sends all requests through a custom HTTP client, allowing options
+    /// such as proxies and timeouts to be customized
+    ///
+    /// NOTE: the client should not follow redirects automatically, since
+    ///       OneDrive uses them to hand out temporary download URLs
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - HTTP client to use for all requests
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Constructs the configured OneDrive client
+    pub fn build(self) -> OneDrive {
+        let client = self.client.unwrap_or_else(|| {
+            Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .gzip(true)
+                .build()
+                .unwrap()
+        });
+        OneDrive {
+            api: Arc::new(OneDriveApi {
+                client,
+                access_token: self.access_token,
+                base_url: self.base_url,
+            }),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -20,7 +252,7 @@ pub struct OneDriveApi {
 /// All subsequent OneDrive operations are expected to be initiated
 /// through this struct
 pub struct OneDrive {
-    api: Rc<OneDriveApi>,
+    api: Arc<OneDriveApi>,
 }
 
 impl OneDrive {
@@ -30,31 +262,108 @@ impl OneDrive {
     ///
     /// * `token` - API key used to authenticate with.
     pub fn new(token: &str) -> Self {
-        let client = Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .gzip(true)
-            .build()
-            .unwrap();
-        OneDrive {
-            api: Rc::new(OneDriveApi {
-                client,
-                access_token: token.to_string(),
-            }),
-        }
+        OneDrive::builder(token).build()
+    }
+
+    /// Gets a builder for customizing the behavior of the client
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - API key used to authenticate with.
+    pub fn builder(token: &str) -> OneDriveBuilder {
+        OneDriveBuilder::new(token)
     }
 
     /// Retrieves profile data for the currently logged in user
     pub async fn me(&self) -> MyResult<User> {
         // Requires user.read scope
-        let url = format!("{}{}", ONEDRIVE_API_URL, "/me");
-        let opt_resp = self
-            .api
-            .client
-            .get(&url)
-            .bearer_auth(&self.api.access_token)
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(User::new(opt_resp, &url, Rc::clone(&self.api)).await)
+        let url = self.api.url("/me");
+        let opt_resp = self.api.get(&url).await?;
+        User::new(opt_resp, &url, Arc::clone(&self.api)).await
+    }
+
+    /// Retrieves the default drive for the currently logged in user
+    pub async fn drive(&self) -> MyResult<Drive> {
+        let url = self.api.url("/me/drive");
+        let opt_resp = self.api.get(&url).await?;
+        Drive::new(opt_resp, &url, Arc::clone(&self.api)).await
+    }
+
+    /// Gets a file or folder from the default drive of the currently logged
+    /// in user by its path, without first loading the drive itself
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item, relative to the root folder of the drive
+    ///            ie: "/Documents/Budget.xlsx"
+    pub async fn item_by_path(&self, path: &str) -> MyResult<DriveItem> {
+        get_item_by_path(&self.api, &self.api.url("/me/drive"), path).await
+    }
+
+    /// Gets a file or folder from the default drive of the currently logged
+    /// in user by its unique identifier, without first loading the drive
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Unique identifier of the item within the drive
+    pub async fn item_by_id(&self, id: &str) -> MyResult<DriveItem> {
+        get_item_by_id(&self.api, &self.api.url("/me/drive"), id).await
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::driveitem::{ConflictBehavior, DriveItemStream};
+    use crate::api::upload::UploadSession;
+
+    /// Compile time check verifying a type can be shared across threads
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    /// Compile time check verifying a value can be moved across threads
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn api_urls() {
+        let service = OneDrive::new("abcd");
+        assert_eq!(
+            service.api.url("/me"),
+            "https://graph.microsoft.com/v1.0/me"
+        );
+        let service = OneDrive::builder("abcd")
+            .base_url("http://127.0.0.1:1234/")
+            .build();
+        assert_eq!(service.api.url("/me"), "http://127.0.0.1:1234/me");
+    }
+
+    #[test]
+    fn entities_are_thread_safe() {
+        assert_send_sync::<OneDrive>();
+        assert_send_sync::<User>();
+        assert_send_sync::<Drive>();
+        assert_send_sync::<DriveItem>();
+        assert_send_sync::<UploadSession>();
+
+        // Streams are consumed by one task at a time, so they only need to
+        // be transferable between threads
+        fn assert_stream_send<T: Send>() {}
+        assert_stream_send::<DriveItemStream>();
+    }
+
+    #[test]
+    fn futures_are_send() {
+        // Futures are never polled here; we only need to verify they can be
+        // passed to tokio::spawn
+        let service = OneDrive::new("abcd");
+        assert_send(&service.me());
+        assert_send(&service.drive());
+
+        let item: DriveItem = serde_json::from_str(r#"{"id": "1", "name": "a"}"#).unwrap();
+        assert_send(&item.reload());
+        assert_send(&item.child("b.txt"));
+        assert_send(&item.create_upload_session("b.txt", ConflictBehavior::Fail));
     }
 }
@@ -0,0 +1,122 @@
+//! Primitives for transferring file content to OneDrive
+use std::cmp::min;
+use std::error::Error;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_RANGE;
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+use simple_error::SimpleError;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::driveitem::DriveItem;
+use super::onedrive::OneDriveApi;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Number of bytes sent to OneDrive in each request of an upload session
+/// OneDrive requires fragments to be a multiple of 320 KiB in size
+pub const CHUNK_SIZE: usize = 320 * 1024 * 32;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Abstraction around a resumable upload session which allows large files
+/// to be transferred to OneDrive in several smaller fragments
+/// See API docs for more details
+///     https://docs.microsoft.com/en-us/graph/api/driveitem-createuploadsession
+pub struct UploadSession {
+    pub upload_url: String,
+    pub expiration_date_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub next_expected_ranges: Vec<String>,
+    #[serde(skip)]
+    api: Option<Arc<OneDriveApi>>,
+}
+
+impl UploadSession {
+    /// Constructs new instances of the UploadSession struct
+    ///
+    /// # Arguments
+    ///
+    /// * `resp` - HTTP response data loaded from the OneDrive API
+    /// * `api` - Shared reference to the interface used to communicate with
+    ///           the OneDrive REST API
+    pub(crate) async fn new(resp: Response, api: Arc<OneDriveApi>) -> MyResult<UploadSession> {
+        let mut retval: UploadSession = resp.json().await?;
+        retval.api = Some(api);
+        Ok(retval)
+    }
+
+    /// Helper method that unwraps a reference to the REST API interface used
+    /// by the impl for making dynamic API calls. Fails for entities which
+    /// were not loaded through a client, such as those deserialized by callers
+    fn api(&self) -> MyResult<&Arc<OneDriveApi>> {
+        self.api
+            .as_ref()
+            .ok_or_else(|| SimpleError::new("Upload session is not bound to a client").into())
+    }
+
+    /// Uploads a single fragment of a file to OneDrive
+    ///
+    /// Returns the newly created drive item once the last fragment of the
+    /// file has been received by OneDrive, or None if more data is expected
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Content of the fragment to upload. Must not be empty
+    /// * `offset` - Position of the first byte of the fragment within the file
+    /// * `file_size` - Total size of the file being uploaded, in bytes
+    pub async fn upload_part(
+        &self,
+        data: Vec<u8>,
+        offset: u64,
+        file_size: u64,
+    ) -> MyResult<Option<DriveItem>> {
+        let end = offset + data.len() as u64 - 1;
+        // NOTE: upload URLs are pre-authenticated, so we must not provide
+        //       our access token with these requests
+        let opt_resp = self
+            .api()?
+            .client
+            .put(&self.upload_url)
+            .header(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{}", offset, end, file_size),
+            )
+            .body(data)
+            .send()
+            .await?
+            .error_for_status()?;
+        if opt_resp.status() == StatusCode::ACCEPTED {
+            return Ok(None);
+        }
+        Ok(Some(
+            DriveItem::new(opt_resp, Arc::clone(self.api()?)).await?,
+        ))
+    }
+
+    /// Uploads the entire content of a file to OneDrive, one fragment at a time
+    /// Only a single fragment of the file is held in memory at once
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the file content
+    /// * `file_size` - Total number of bytes to read from the source
+    pub async fn upload_from<R: AsyncRead + Send + Unpin + ?Sized>(
+        &self,
+        reader: &mut R,
+        file_size: u64,
+    ) -> MyResult<DriveItem> {
+        let mut offset = 0;
+        while offset < file_size {
+            let mut buffer = vec![0; min(CHUNK_SIZE as u64, file_size - offset) as usize];
+            reader.read_exact(&mut buffer).await?;
+            let length = buffer.len() as u64;
+            if let Some(item) = self.upload_part(buffer, offset, file_size).await? {
+                return Ok(item);
+            }
+            offset += length;
+        }
+        Err(SimpleError::new("OneDrive did not acknowledge completion of the upload").into())
+    }
+}
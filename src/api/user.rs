@@ -1,13 +1,15 @@
 //! Primitives for manipulating OneDrive user entities
 use std::error::Error;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use reqwest::Response;
 use serde::Deserialize;
+use simple_error::SimpleError;
 
 use super::drive::Drive;
+use super::driveitem::DriveItem;
 use super::onedrive::OneDriveApi;
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,22 +18,23 @@ type MyResult<T> = Result<T, Box<dyn Error>>;
 ///     https://docs.microsoft.com/en-ca/graph/api/resources/user
 pub struct User {
     #[serde(rename = "@odata.context")]
-    pub context: String,
+    pub context: Option<String>,
+    #[serde(default)]
     pub business_phones: Vec<String>,
     pub display_name: String,
-    pub given_name: String,
+    pub given_name: Option<String>,
     pub id: String,
     pub job_title: Option<String>,
     pub mail: Option<String>,
     pub mobile_phone: Option<String>,
     pub offline_location: Option<String>,
     pub preferred_language: Option<String>,
-    pub surname: String,
+    pub surname: Option<String>,
     pub user_principal_name: String,
     #[serde(skip)]
     url: String,
     #[serde(skip)]
-    api: Option<Rc<OneDriveApi>>,
+    api: Option<Arc<OneDriveApi>>,
 }
 
 impl User {
@@ -43,33 +46,35 @@ impl User {
     /// * `url` - Full URL to the REST API endpoint managed by this entity
     /// * `api` - Shared reference to the interface used to communicate with
     ///           the OneDrive REST API
-    pub async fn new(resp: Response, url: &str, api: Rc<OneDriveApi>) -> User {
-        let mut retval: User = resp.json().await.unwrap();
+    pub(crate) async fn new(resp: Response, url: &str, api: Arc<OneDriveApi>) -> MyResult<User> {
+        let mut retval: User = resp.json().await?;
         retval.url = url.to_string();
         retval.api = Some(api);
-        retval
+        Ok(retval)
     }
 
     /// Helper method that unwraps a reference to the REST API interface used
-    /// by the impl for making dynamic API calls
-    fn api(&self) -> &Rc<OneDriveApi> {
-        self.api.as_ref().unwrap()
+    /// by the impl for making dynamic API calls. Fails for entities which
+    /// were not loaded through a client, such as those deserialized by callers
+    fn api(&self) -> MyResult<&Arc<OneDriveApi>> {
+        self.api
+            .as_ref()
+            .ok_or_else(|| SimpleError::new("User is not bound to a client").into())
     }
 
-    /// Gets a reference to the root drive associaed with this user
-    /// This interface provides the tools needed to interact with
-    /// files and folders contained within the drive
-    pub async fn root(&self) -> MyResult<Drive> {
-        // TODO: figure out how to lazy load properties in a struct
+    /// Gets the default drive associated with this user
+    pub async fn drive(&self) -> MyResult<Drive> {
+        let url = format!("{}{}", self.url, "/drive");
+        let opt_resp = self.api()?.get(&url).await?;
+        Drive::new(opt_resp, &url, Arc::clone(self.api()?)).await
+    }
+
+    /// Gets a reference to the root folder of the drive associated with
+    /// this user. This interface provides the tools needed to interact
+    /// with files and folders contained within the drive
+    pub async fn root(&self) -> MyResult<DriveItem> {
         let url = format!("{}{}", self.url, "/drive/root");
-        let opt_resp = self
-            .api()
-            .client
-            .get(&url)
-            .bearer_auth(&self.api().access_token)
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(Drive::new(opt_resp, &url, Rc::clone(self.api())).await)
+        let opt_resp = self.api()?.get(&url).await?;
+        DriveItem::new(opt_resp, Arc::clone(self.api()?)).await
     }
 }
@@ -2,12 +2,11 @@
 //! Used primarily to orchestrate the OAuth authentication
 //! process using the "code flow" defined here:
 //!     https://docs.microsoft.com/en-us/onedrive/developer/rest-api/getting-started/msa-oauth?view=odsp-graph-online
-use futures::executor::block_on;
 use serde::Deserialize;
 use simple_error::SimpleError;
-use std::io::{BufRead, BufReader, Write};
-use std::net::TcpListener;
 use std::{collections::HashMap, error::Error};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
 use url::Url;
 use urlencoding::encode;
 
@@ -18,12 +17,21 @@ pub const REDIRECT_URI: &str = "http://127.0.0.1:8080/";
 /// Managed through the Azue app port here:
 ///     https://portal.azure.com/#view/Microsoft_AAD_RegisteredApps/ApplicationsListBlade
 const CLIENT_ID: &str = "454dddcf-522d-43b6-b078-b38657e8045a";
+/// Base URL of the Microsoft OAuth service used to authenticate OneDrive users
+pub const DEFAULT_AUTH_URL: &str = "https://login.live.com";
 
 /// Gets a formatted URL that can be pasted into a web browser to request access
 /// to the currently logged in OneDrive users profile for our app
-pub fn get_auth_url() -> String {
+///
+/// # Arguments
+///
+/// * `auth_url` - Base URL of the OAuth service to authenticate with
+pub fn get_auth_url(auth_url: &str) -> String {
     let scope = encode("files.readwrite.all onedrive.readwrite offline_access user.read");
-    format!("https://login.live.com/oauth20_authorize.srf?client_id={}&scope={}&response_type=code&redirect_uri={}", CLIENT_ID, scope, REDIRECT_URI)
+    format!(
+        "{}/oauth20_authorize.srf?client_id={}&scope={}&response_type=code&redirect_uri={}",
+        auth_url, CLIENT_ID, scope, REDIRECT_URI
+    )
 }
 
 /// Parses a short lived authentication token from a URL which is generated
@@ -35,7 +43,7 @@ pub fn get_auth_url() -> String {
 /// * `url` - Response URL produced by the OneDrive authentication process
 ///           Is expected to have a short lived authentication token encoded
 ///           in a query parameter named "code"
-pub fn parse_token(url: &str) -> Result<String, Box<dyn Error>> {
+pub fn parse_token(url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
     let url_data = Url::parse(url)?;
     for pair in url_data.query_pairs() {
         if pair.0 == "code" {
@@ -48,37 +56,38 @@ pub fn parse_token(url: &str) -> Result<String, Box<dyn Error>> {
 /// Retrieves an oauth token for the OneDrive service for the user by opening
 /// the OAuth registration page in the default web browser and listening for
 /// an approved response from the default listening port on the local machine
-pub fn get_oauth_token_from_browser() -> Result<String, Box<dyn Error>> {
+///
+/// # Arguments
+///
+/// * `auth_url` - Base URL of the OAuth service to authenticate with
+pub async fn get_oauth_token_from_browser(
+    auth_url: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
     // TODO: Write tests for this code
     // Reference implementation:
     // https://github.com/ramosbugs/oauth2-rs/blob/main/examples/msgraph.rs
-    let listener = TcpListener::bind("127.0.0.1:8080")?;
-    open::that(get_auth_url())?;
-
-    let mut params = String::new();
-    if let Some(stream) = listener.incoming().next() {
-        let mut stream = stream?;
-
-        let mut reader = BufReader::new(&stream);
-
-        let mut request_line = String::new();
-        reader.read_line(&mut request_line)?;
-
-        let temp_err = SimpleError::new(format!("Unvalid input line {}", request_line));
-        let redirect_url = request_line.split_whitespace().nth(1).ok_or(temp_err)?;
-        params.push_str(redirect_url);
-
-        // TODO: update this response to pop up a modal dialog in the browser informing
-        //       the user to go back to the terminal, and then force-close the browser tab
-        let content = "Go back to your terminal :)";
-        let response = format!(
-            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
-            content.len(),
-            content
-        );
-        stream.write_all(response.as_bytes())?;
-    }
-    Ok(format!("{}{}", REDIRECT_URI, params))
+    let listener = TcpListener::bind("127.0.0.1:8080").await?;
+    open::that(get_auth_url(auth_url))?;
+
+    let (mut stream, _) = listener.accept().await?;
+    let mut reader = BufReader::new(&mut stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    let temp_err = SimpleError::new(format!("Unvalid input line {}", request_line));
+    let redirect_url = request_line.split_whitespace().nth(1).ok_or(temp_err)?;
+
+    // TODO: update this response to pop up a modal dialog in the browser informing
+    //       the user to go back to the terminal, and then force-close the browser tab
+    let content = "Go back to your terminal :)";
+    let response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(format!("{}{}", REDIRECT_URI, redirect_url))
 }
 
 #[derive(Deserialize, Debug)]
@@ -97,21 +106,30 @@ pub struct Authdata {
 ///
 /// # Arguments
 ///
+/// * `auth_url` - Base URL of the OAuth service to authenticate with
 /// * `client_code` - temporary authentication code provided by OneDrive after
 ///                   the user has accepted the authentication request for
 ///                   the application
-pub fn get_auth_data(client_code: &str) -> Result<Authdata, Box<dyn Error>> {
+pub async fn get_auth_data(
+    auth_url: &str,
+    client_code: &str,
+) -> Result<Authdata, Box<dyn Error + Send + Sync>> {
     let client = reqwest::Client::new();
 
-    let url = "https://login.live.com/oauth20_token.srf";
+    let url = format!("{}/oauth20_token.srf", auth_url);
     let mut params = HashMap::new();
     params.insert("client_id", CLIENT_ID);
     params.insert("redirect_uri", REDIRECT_URI);
     params.insert("code", client_code);
     params.insert("grant_type", "authorization_code");
 
-    let response = block_on(client.post(url).form(&params).send())?;
-    let data: Authdata = block_on(response.json::<Authdata>())?;
+    let response = client
+        .post(url)
+        .form(&params)
+        .send()
+        .await?
+        .error_for_status()?;
+    let data: Authdata = response.json::<Authdata>().await?;
     Ok(data)
 }
 
@@ -120,21 +138,30 @@ pub fn get_auth_data(client_code: &str) -> Result<Authdata, Box<dyn Error>> {
 ///
 /// # Arguments
 ///
+/// * `auth_url` - Base URL of the OAuth service to authenticate with
 /// * `refresh_token` - temporary authentication token loaded previously which allows
 ///                     us to request a new, longer term use auth token from OneDrive
 ///                     Returned auth data will include a new refresh token for use
 ///                     in subsequent calls
-pub fn refresh_auth_data(refresh_token: &str) -> Result<Authdata, Box<dyn Error>> {
+pub async fn refresh_auth_data(
+    auth_url: &str,
+    refresh_token: &str,
+) -> Result<Authdata, Box<dyn Error + Send + Sync>> {
     let client = reqwest::Client::new();
 
-    let url = "https://login.live.com/oauth20_token.srf";
+    let url = format!("{}/oauth20_token.srf", auth_url);
 
     let mut params = HashMap::new();
     params.insert("client_id", CLIENT_ID);
     params.insert("redirect_uri", REDIRECT_URI);
     params.insert("refresh_token", refresh_token);
     params.insert("grant_type", "refresh_token");
-    let response = block_on(client.post(url).form(&params).send())?;
-    let data: Authdata = block_on(response.json::<Authdata>())?;
+    let response = client
+        .post(url)
+        .form(&params)
+        .send()
+        .await?
+        .error_for_status()?;
+    let data: Authdata = response.json::<Authdata>().await?;
     Ok(data)
 }
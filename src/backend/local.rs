@@ -0,0 +1,247 @@
+//! Storage backend for folders on the local file system
+use std::error::Error;
+use std::fs::Metadata;
+use std::path::{Component, Path, PathBuf};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use simple_error::SimpleError;
+use tempfile::NamedTempFile;
+use tokio::fs::{self, File};
+use tokio::io::{copy, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{Backend, Entry};
+use crate::paths::join_path;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Backend which stores items within a folder on the local file system
+#[derive(Debug, Clone)]
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    /// Constructs a new instance of the local backend
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Folder on the local file system containing all items
+    ///            managed by the backend
+    pub fn new(root: &Path) -> Self {
+        LocalBackend {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Converts a backend path to a location on the local file system
+    /// Paths that would escape the root folder of the backend are rejected
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item, relative to the root of the backend
+    fn resolve(&self, path: &str) -> MyResult<PathBuf> {
+        let relative = Path::new(path.trim_start_matches('/'));
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(SimpleError::new(format!("Invalid path {}", path)).into());
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Generates the metadata describing an item on the local file system
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item, relative to the root of the backend
+    /// * `meta` - File system metadata for the item
+    fn entry(path: &str, meta: &Metadata) -> Entry {
+        let path = format!("/{}", path.trim_matches('/'));
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
+        Entry {
+            path,
+            name,
+            is_folder: meta.is_dir(),
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            modified: meta.modified().ok().map(DateTime::<Utc>::from),
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for LocalBackend {
+    async fn list(&self, path: &str) -> MyResult<Vec<Entry>> {
+        let mut retval = Vec::new();
+        let mut entries = fs::read_dir(self.resolve(path)?).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let meta = entry.metadata().await?;
+            retval.push(LocalBackend::entry(&join_path(path, &name), &meta));
+        }
+        retval.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(retval)
+    }
+
+    async fn stat(&self, path: &str) -> MyResult<Entry> {
+        let meta = fs::metadata(self.resolve(path)?).await?;
+        Ok(LocalBackend::entry(path, &meta))
+    }
+
+    async fn read(
+        &self,
+        path: &str,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> MyResult<u64> {
+        let mut file = File::open(self.resolve(path)?).await?;
+        Ok(copy(&mut file, writer).await?)
+    }
+
+    async fn write(
+        &self,
+        path: &str,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        size: u64,
+    ) -> MyResult<Entry> {
+        // Content is written to a temporary file in the same folder, so any
+        // existing file is only replaced once all of its content is received
+        let local = self.resolve(path)?;
+        let folder = local.parent().unwrap_or(&self.root);
+        let temp = NamedTempFile::new_in(folder)?;
+        let mut file = File::from_std(temp.reopen()?);
+        let written = copy(&mut reader.take(size), &mut file).await?;
+        file.flush().await?;
+        if written < size {
+            let msg = format!(
+                "Expected {} bytes for {} but received {}",
+                size, path, written
+            );
+            return Err(SimpleError::new(msg).into());
+        }
+        let temp_path = temp.into_temp_path();
+        fs::rename(&temp_path, &local).await?;
+        temp_path.keep()?;
+        self.stat(path).await
+    }
+
+    async fn mkdir(&self, path: &str) -> MyResult<Entry> {
+        fs::create_dir(self.resolve(path)?).await?;
+        self.stat(path).await
+    }
+
+    async fn delete(&self, path: &str) -> MyResult<()> {
+        let local = self.resolve(path)?;
+        if local == self.root {
+            return Err(SimpleError::new("Refusing to delete the root folder").into());
+        }
+        match fs::metadata(&local).await?.is_dir() {
+            true => fs::remove_dir_all(local).await?,
+            false => fs::remove_file(local).await?,
+        }
+        Ok(())
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> MyResult<Entry> {
+        fs::rename(self.resolve(from)?, self.resolve(to)?).await?;
+        self.stat(to).await
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn write_and_read_files() {
+        let temp_dir = tempdir().unwrap();
+        let backend = LocalBackend::new(temp_dir.path());
+
+        let entry = backend
+            .write("/a.txt", &mut &b"hello"[..], 5)
+            .await
+            .unwrap();
+        assert_eq!(entry.path, "/a.txt");
+        assert_eq!(entry.name, "a.txt");
+        assert_eq!(entry.size, 5);
+        assert!(!entry.is_folder);
+        assert!(entry.modified.is_some());
+        let mut data = Vec::new();
+        assert_eq!(backend.read("/a.txt", &mut data).await.unwrap(), 5);
+        assert_eq!(data, b"hello");
+
+        // Sources with less data than expected must not leave a silently
+        // truncated file behind
+        assert!(backend.write("/b.txt", &mut &b"hi"[..], 5).await.is_err());
+        assert!(backend.stat("/b.txt").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn short_write_keeps_existing_file() {
+        let temp_dir = tempdir().unwrap();
+        let backend = LocalBackend::new(temp_dir.path());
+        backend
+            .write("/a.txt", &mut &b"original"[..], 8)
+            .await
+            .unwrap();
+
+        assert!(backend.write("/a.txt", &mut &b"new"[..], 8).await.is_err());
+        let mut data = Vec::new();
+        backend.read("/a.txt", &mut data).await.unwrap();
+        assert_eq!(data, b"original");
+        assert_eq!(backend.list("/").await.unwrap().len(), 1);
+
+        backend.write("/a.txt", &mut &b"new"[..], 3).await.unwrap();
+        data.clear();
+        backend.read("/a.txt", &mut data).await.unwrap();
+        assert_eq!(data, b"new");
+    }
+
+    #[tokio::test]
+    async fn list_folders() {
+        let temp_dir = tempdir().unwrap();
+        let backend = LocalBackend::new(temp_dir.path());
+        backend.mkdir("/docs").await.unwrap();
+        backend
+            .write("/docs/b.txt", &mut &b""[..], 0)
+            .await
+            .unwrap();
+        backend.mkdir("/docs/a").await.unwrap();
+
+        let entries = backend.list("/docs").await.unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/docs/a", "/docs/b.txt"]);
+        assert!(entries[0].is_folder);
+
+        let root = backend.list("/").await.unwrap();
+        assert_eq!(root.len(), 1);
+        assert_eq!(root[0].path, "/docs");
+    }
+
+    #[tokio::test]
+    async fn rename_and_delete() {
+        let temp_dir = tempdir().unwrap();
+        let backend = LocalBackend::new(temp_dir.path());
+        backend.mkdir("/docs").await.unwrap();
+        backend.write("/a.txt", &mut &b""[..], 0).await.unwrap();
+
+        let entry = backend.rename("/a.txt", "/docs/b.txt").await.unwrap();
+        assert_eq!(entry.path, "/docs/b.txt");
+        assert!(backend.stat("/a.txt").await.is_err());
+
+        backend.delete("/docs").await.unwrap();
+        assert!(backend.list("/").await.unwrap().is_empty());
+        assert!(backend.delete("/").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reject_paths_outside_root() {
+        let temp_dir = tempdir().unwrap();
+        let backend = LocalBackend::new(&temp_dir.path().join("root"));
+        assert!(backend.stat("/../secret.txt").await.is_err());
+        assert!(backend.write("../a.txt", &mut &b""[..], 0).await.is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Abstraction over the storage services files can be transferred between
+//!
+//! Every storage service implements the [`Backend`] trait, allowing logic
+//! such as copying folder hierarchies to be written once and used with any
+//! pair of services. Paths are always "/" separated and relative to the
+//! root of the backend, ie: "/Documents/Budget.xlsx"
+//!
+//! The trait only covers what every service can support, namely plain files
+//! and folders. The CLI subcommands that manage OneDrive items, such as 'ls'
+//! or 'rm', still use the OneDrive client directly since they rely on
+//! features with no local equivalent, including item identifiers, conflict
+//! behaviors, sharing details and the recycle bin
+use std::error::Error;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::{try_join, BoxFuture};
+use futures::FutureExt;
+use tokio::io::{duplex, AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use crate::paths::join_path;
+
+pub mod local;
+pub mod onedrive;
+
+pub use self::local::LocalBackend;
+pub use self::onedrive::OneDriveBackend;
+
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Number of bytes buffered between the backends while copying a file
+const PIPE_SIZE: usize = 64 * 1024;
+
+/// Metadata describing a single file or folder stored in a backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Full path to the item, relative to the root of the backend
+    pub path: String,
+    /// Name of the item, without the path of its parent folder
+    pub name: String,
+    /// True if the item is a folder, false if it is a file
+    pub is_folder: bool,
+    /// Size of the item in bytes
+    pub size: u64,
+    /// Date and time the item was last modified, if known
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Operations supported by every storage service
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Gets the items contained directly within a folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the folder to list
+    async fn list(&self, path: &str) -> MyResult<Vec<Entry>>;
+
+    /// Gets metadata describing a single file or folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item to describe
+    async fn stat(&self, path: &str) -> MyResult<Entry>;
+
+    /// Writes the content of a file to the given output, a piece at a time
+    /// Returns the number of bytes written
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the file to read
+    /// * `writer` - Destination for the file content
+    async fn read(&self, path: &str, writer: &mut (dyn AsyncWrite + Send + Unpin))
+        -> MyResult<u64>;
+
+    /// Creates a file from the content of the given source, replacing any
+    /// existing file with the same path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the file to write. The parent folder must exist
+    /// * `reader` - Source of the file content
+    /// * `size` - Number of bytes to read from the source
+    async fn write(
+        &self,
+        path: &str,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        size: u64,
+    ) -> MyResult<Entry>;
+
+    /// Creates a new, empty folder
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the folder to create. The parent folder must exist
+    async fn mkdir(&self, path: &str) -> MyResult<Entry>;
+
+    /// Deletes a file, or a folder along with all of its contents
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item to delete
+    async fn delete(&self, path: &str) -> MyResult<()>;
+
+    /// Moves an item to a new location, which may also rename it
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Path to the item to move
+    /// * `to` - New path for the item. The parent folder must exist
+    async fn rename(&self, from: &str, to: &str) -> MyResult<Entry>;
+}
+
+/// Copies a file, or a folder along with all of its contents, from one
+/// backend to another. Existing files at the destination are replaced
+/// Returns the number of files copied
+///
+/// # Arguments
+///
+/// * `source` - Backend containing the item to copy
+/// * `source_path` - Path to the item to copy
+/// * `dest` - Backend to copy the item to
+/// * `dest_path` - Path the item is to be copied to
+pub fn copy<'a>(
+    source: &'a dyn Backend,
+    source_path: &'a str,
+    dest: &'a dyn Backend,
+    dest_path: &'a str,
+) -> BoxFuture<'a, MyResult<u64>> {
+    async move {
+        let entry = source.stat(source_path).await?;
+        if !entry.is_folder {
+            // Content is streamed through a small buffer shared by both
+            // backends, so files are never loaded into memory in full
+            let (mut reader, mut writer) = duplex(PIPE_SIZE);
+            let read = async move {
+                source.read(source_path, &mut writer).await?;
+                writer.shutdown().await?;
+                Ok(())
+            };
+            // Each half owns its end of the pipe, so a failure on one side
+            // closes the pipe rather than leaving the other side waiting
+            let write = async move { dest.write(dest_path, &mut reader, entry.size).await };
+            try_join(read, write).await?;
+            return Ok(1);
+        }
+
+        // Folders that already exist at the destination are merged
+        match dest.stat(dest_path).await {
+            Ok(existing) if existing.is_folder => {}
+            _ => {
+                dest.mkdir(dest_path).await?;
+            }
+        }
+        let mut count = 0;
+        for child in source.list(source_path).await? {
+            let child_dest = join_path(dest_path, &child.name);
+            count += copy(source, &child.path, dest, &child_dest).await?;
+        }
+        Ok(count)
+    }
+    .boxed()
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string, write};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn copy_between_backends() {
+        let source_dir = tempdir().unwrap();
+        let dest_dir = tempdir().unwrap();
+        create_dir_all(source_dir.path().join("docs/nested")).unwrap();
+        write(source_dir.path().join("docs/a.txt"), "a").unwrap();
+        write(source_dir.path().join("docs/nested/b.txt"), "b").unwrap();
+
+        let source = LocalBackend::new(source_dir.path());
+        let dest = LocalBackend::new(dest_dir.path());
+        let count = copy(&source, "/docs", &dest, "/backup").await.unwrap();
+
+        assert_eq!(count, 2);
+        let backup = dest_dir.path().join("backup");
+        assert_eq!(read_to_string(backup.join("a.txt")).unwrap(), "a");
+        assert_eq!(read_to_string(backup.join("nested/b.txt")).unwrap(), "b");
+
+        // Copying again should merge with the existing folders
+        write(source_dir.path().join("docs/a.txt"), "changed").unwrap();
+        let count = copy(&source, "/docs", &dest, "/backup").await.unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(read_to_string(backup.join("a.txt")).unwrap(), "changed");
+    }
+
+    #[tokio::test]
+    async fn copy_single_file() {
+        let source_dir = tempdir().unwrap();
+        let dest_dir = tempdir().unwrap();
+        write(source_dir.path().join("a.txt"), "hello").unwrap();
+
+        let source = LocalBackend::new(source_dir.path());
+        let dest = LocalBackend::new(dest_dir.path());
+        let count = copy(&source, "/a.txt", &dest, "/b.txt").await.unwrap();
+
+        assert_eq!(count, 1);
+        let actual = read_to_string(dest_dir.path().join("b.txt")).unwrap();
+        assert_eq!(actual, "hello");
+    }
+}
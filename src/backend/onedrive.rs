@@ -0,0 +1,115 @@
+//! Storage backend for the default drive of a OneDrive user
+use std::error::Error;
+
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use super::{Backend, Entry};
+use crate::api::driveitem::{ConflictBehavior, DriveItem, ListOptions};
+use crate::api::OneDrive;
+use crate::paths::{join_path, split_path};
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Backend which stores items in the default drive of a OneDrive user
+#[derive(Debug)]
+pub struct OneDriveBackend {
+    service: OneDrive,
+}
+
+impl OneDriveBackend {
+    /// Constructs a new instance of the OneDrive backend
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - Client used to communicate with OneDrive
+    pub fn new(service: OneDrive) -> Self {
+        OneDriveBackend { service }
+    }
+
+    /// Generates the metadata describing a OneDrive item
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the item, relative to the root of the drive
+    /// * `item` - OneDrive metadata for the item
+    fn entry(path: &str, item: &DriveItem) -> Entry {
+        Entry {
+            path: format!("/{}", path.trim_matches('/')),
+            name: item.name.clone(),
+            is_folder: item.is_folder(),
+            size: item.size.unwrap_or_default(),
+            modified: item.last_modified_date_time,
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for OneDriveBackend {
+    async fn list(&self, path: &str) -> MyResult<Vec<Entry>> {
+        let folder = self.service.item_by_path(path).await?;
+        let children: Vec<DriveItem> = folder
+            .children(&ListOptions::default())
+            .try_collect()
+            .await?;
+        Ok(children
+            .iter()
+            .map(|i| OneDriveBackend::entry(&join_path(path, &i.name), i))
+            .collect())
+    }
+
+    async fn stat(&self, path: &str) -> MyResult<Entry> {
+        let item = self.service.item_by_path(path).await?;
+        Ok(OneDriveBackend::entry(path, &item))
+    }
+
+    async fn read(
+        &self,
+        path: &str,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> MyResult<u64> {
+        let item = self.service.item_by_path(path).await?;
+        let mut opt_resp = item.download().await?;
+        let mut total = 0;
+        while let Some(chunk) = opt_resp.chunk().await? {
+            writer.write_all(&chunk).await?;
+            total += chunk.len() as u64;
+        }
+        Ok(total)
+    }
+
+    async fn write(
+        &self,
+        path: &str,
+        reader: &mut (dyn AsyncRead + Send + Unpin),
+        size: u64,
+    ) -> MyResult<Entry> {
+        let (parent, name) = split_path(path)?;
+        let folder = self.service.item_by_path(parent).await?;
+        let item = folder
+            .upload_from(name, reader, size, ConflictBehavior::Replace)
+            .await?;
+        Ok(OneDriveBackend::entry(path, &item))
+    }
+
+    async fn mkdir(&self, path: &str) -> MyResult<Entry> {
+        let (parent, name) = split_path(path)?;
+        let folder = self.service.item_by_path(parent).await?;
+        let item = folder.create_folder(name, ConflictBehavior::Fail).await?;
+        Ok(OneDriveBackend::entry(path, &item))
+    }
+
+    async fn delete(&self, path: &str) -> MyResult<()> {
+        self.service.item_by_path(path).await?.delete().await
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> MyResult<Entry> {
+        let (parent, name) = split_path(to)?;
+        let item = self.service.item_by_path(from).await?;
+        let folder = self.service.item_by_path(parent).await?;
+        let item = item
+            .move_to(&folder, name, ConflictBehavior::Replace)
+            .await?;
+        Ok(OneDriveBackend::entry(to, &item))
+    }
+}
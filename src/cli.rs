@@ -0,0 +1,552 @@
+//! Command line interface for the app
+//! Parses command line arguments and dispatches them to the handler for
+//! the selected subcommand
+use crate::api::{ByteRange, ConflictBehavior, OneDrive};
+use crate::commands::{
+    cat_cmd, connect, cp_cmd, df_cmd, du_cmd, find_cmd, find_items, init_cmd, ls_cmd, me_cmd,
+    mkdir_cmd, mv_cmd, rm_cmd, search_cmd, stat_cmd, tree_cmd, upload_cmd, SortKey,
+};
+use crate::filter::{parse_date, ItemFilter, SizeThreshold, Threshold};
+use crate::output::{ItemType, Output, OutputFormat};
+use chrono::{DateTime, Utc};
+use clap::{Arg, Parser, Subcommand};
+use glob::Pattern;
+use simple_error::SimpleError;
+use std::{env, error::Error, fmt::Debug, path::PathBuf, process::ExitCode};
+
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Values accepted by options that control how name collisions are handled
+const CONFLICT_OPTIONS: [&str; 3] = ["fail", "replace", "rename"];
+
+/// Values accepted by the option that selects the output format
+const OUTPUT_OPTIONS: [&str; 4] = ["plain", "table", "json", "ndjson"];
+
+/// Values accepted by options that control the order items are shown in
+const SORT_OPTIONS: [&str; 3] = ["name", "size", "time"];
+
+/// Values accepted by options that select the kind of items to include
+const TYPE_OPTIONS: [&str; 3] = ["file", "folder", "package"];
+
+/// Values accepted by the 'find' subcommand to select the kind of items to
+/// include, including the single letters used by the 'find' command
+const FIND_TYPE_OPTIONS: [&str; 6] = ["f", "d", "p", "file", "folder", "package"];
+
+/// Options of the 'find' subcommand which, like the predicates of the
+/// 'find' command, may also be given with a single leading dash
+const FIND_PREDICATES: [&str; 8] = [
+    "name", "iname", "type", "size", "mtime", "maxdepth", "print0", "exec",
+];
+
+/// Argument marking the end of the subcommand run by the 'find' subcommand
+const EXEC_TERMINATOR: &str = ";";
+
+/// Placeholder replaced with the path of each item found by the 'find'
+/// subcommand, in the arguments of the subcommand it runs
+const EXEC_PLACEHOLDER: &str = "{}";
+
+/// App for managing files on a OneDrive service
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct Args {
+    #[clap(subcommand)]
+    cmd: SubCommand,
+    #[clap(short, long, global = true, default_value = "plain", possible_values = OUTPUT_OPTIONS)]
+    /// Format used to report the results of the command
+    output: OutputFormat,
+}
+
+#[derive(Subcommand, Debug)]
+enum SubCommand {
+    /// Initialize and authenticate the app
+    Init {
+        #[clap(short, long)]
+        /// Can we intercept authentication requests from the browser?
+        browser: bool,
+    },
+    /// List the contents of a OneDrive folder
+    // Like the 'ls' command, -h is reserved for human readable sizes
+    #[clap(arg(Arg::new("help").long("help").help("Print help information")))]
+    Ls {
+        #[clap(default_value = "/")]
+        /// Path to the OneDrive folder to list, or its unique identifier
+        /// prefixed with "id:"
+        path: String,
+        #[clap(short)]
+        /// Show the type, size, modification details and ID of each item
+        long: bool,
+        #[clap(short = 'h', long = "human-readable")]
+        /// Show sizes in human readable form, ie: 1.5M
+        human: bool,
+        #[clap(short, long)]
+        /// Include hidden items and packages such as OneNote notebooks
+        all: bool,
+        #[clap(long, default_value = "name", possible_values = SORT_OPTIONS)]
+        /// Order in which to show the items
+        sort: SortKey,
+        #[clap(short = 'R', long)]
+        /// List the contents of all subfolders as well
+        recursive: bool,
+    },
+    /// Upload a new file to OneDrive
+    Upload {
+        #[clap(required_unless_present = "sourcefile")]
+        /// Path to the local file to upload, or "-" to upload the content
+        /// of stdin, ie: tar cz docs | onedrive_manager upload - /docs.tgz
+        /// Stdin is buffered in a temporary file until it ends, since
+        /// OneDrive needs to know the size of the file
+        source: Option<PathBuf>,
+        #[clap(value_name = "DESTINATION")]
+        /// Existing OneDrive folder to upload the file into, or the path of
+        /// the new file. Defaults to the root folder
+        target: Option<String>,
+        #[clap(short, long, conflicts_with = "source")]
+        /// Path to the file to upload, as an alternative to SOURCE
+        sourcefile: Option<PathBuf>,
+        #[clap(short, long, conflicts_with = "target")]
+        /// Path to the OneDrive folder to upload the file to, as an
+        /// alternative to DESTINATION
+        destination: Option<String>,
+        #[clap(long, default_value = "fail", possible_values = CONFLICT_OPTIONS)]
+        /// How to handle an existing file with the same name
+        conflict: ConflictBehavior,
+    },
+    /// Create a new folder on OneDrive
+    Mkdir {
+        /// Path to the OneDrive folder to create
+        path: String,
+        #[clap(short, long)]
+        /// Create parent folders as needed, and succeed if the folder already exists
+        parents: bool,
+        #[clap(long, default_value = "fail", possible_values = CONFLICT_OPTIONS)]
+        /// How to handle an existing item with the same name
+        conflict: ConflictBehavior,
+    },
+    /// Move or rename a file or folder on OneDrive
+    Mv {
+        /// Path to the OneDrive file or folder to move, or its unique
+        /// identifier prefixed with "id:"
+        source: String,
+        /// Existing folder to move the item into, or the new path of the item
+        destination: String,
+        #[clap(long, default_value = "fail", possible_values = CONFLICT_OPTIONS)]
+        /// How to handle an existing item with the same name
+        conflict: ConflictBehavior,
+    },
+    /// Copy a file or folder to another location on OneDrive
+    Cp {
+        /// Path to the OneDrive file or folder to copy, or its unique
+        /// identifier prefixed with "id:"
+        source: String,
+        /// Existing folder to copy the item into, or the path of the new copy
+        destination: String,
+        #[clap(long)]
+        /// Print the URL reporting the progress of the copy instead of
+        /// waiting for it to complete
+        no_wait: bool,
+        #[clap(long, default_value = "fail", possible_values = CONFLICT_OPTIONS)]
+        /// How to handle an existing item with the same name
+        conflict: ConflictBehavior,
+    },
+    /// Delete a file or folder from OneDrive
+    Rm {
+        /// Path to the OneDrive file or folder to delete
+        path: String,
+        #[clap(short, long)]
+        /// Delete folders along with all of their contents
+        recursive: bool,
+        #[clap(short, long)]
+        /// Delete folders without asking for confirmation
+        yes: bool,
+        #[clap(long)]
+        /// Show what would be deleted without deleting anything
+        dry_run: bool,
+        #[clap(long)]
+        /// Bypass the recycle bin so the item can not be restored
+        /// (OneDrive for Business and SharePoint only)
+        permanent: bool,
+    },
+    /// Write the content of OneDrive files to stdout
+    Cat {
+        #[clap(required = true)]
+        /// Paths to the OneDrive files, or their unique identifiers prefixed
+        /// with "id:"
+        paths: Vec<String>,
+        #[clap(short, long, value_name = "START-END", allow_hyphen_values = true)]
+        /// Only write this range of bytes from each file, ie: "0-1023" for
+        /// the first KiB, "1024-" to skip the first KiB or "-1024" for the
+        /// last KiB
+        range: Option<ByteRange>,
+    },
+    /// Show all of the metadata describing a OneDrive file or folder
+    Stat {
+        /// Path to the OneDrive file or folder, or its unique identifier
+        /// prefixed with "id:"
+        path: String,
+    },
+    /// Shows profile information for the currently logged in user
+    Me,
+    /// Show the storage space used by a OneDrive folder and its subfolders
+    // Like the 'du' command, -h is reserved for human readable sizes
+    #[clap(arg(Arg::new("help").long("help").help("Print help information")))]
+    Du {
+        #[clap(default_value = "/")]
+        /// Path to the OneDrive folder to report on, or its unique identifier
+        /// prefixed with "id:"
+        path: String,
+        #[clap(short, long, default_value = "1")]
+        /// Deepest level of subfolders to show, with 0 showing only the
+        /// folder itself
+        depth: usize,
+        #[clap(short = 'h', long = "human-readable")]
+        /// Show sizes in human readable form, ie: 1.5M
+        human: bool,
+        #[clap(long, possible_values = ["name", "size"])]
+        /// Order in which to show the folders. By default subfolders are
+        /// shown before their parents
+        sort: Option<SortKey>,
+    },
+    /// Show the hierarchy of files and folders below a OneDrive folder
+    // Like the 'tree' command, -h is reserved for human readable sizes
+    #[clap(arg(Arg::new("help").long("help").help("Print help information")))]
+    Tree {
+        #[clap(default_value = "/")]
+        /// Path to the OneDrive folder to show, or its unique identifier
+        /// prefixed with "id:"
+        path: String,
+        #[clap(short = 'L', long)]
+        /// Number of levels of subfolders to show
+        level: Option<usize>,
+        #[clap(short, long)]
+        /// Only show folders
+        dirs_only: bool,
+        #[clap(short, long)]
+        /// Include hidden items and packages such as OneNote notebooks
+        all: bool,
+        #[clap(short, long)]
+        /// Show the size of each item
+        size: bool,
+        #[clap(short = 'h', long = "human-readable")]
+        /// Show the size of each item in human readable form, ie: 1.5M
+        human: bool,
+    },
+    /// Search a OneDrive folder and its subfolders for files and folders
+    Search {
+        /// Text to search for in the names, metadata and content of items
+        query: String,
+        #[clap(default_value = "/")]
+        /// Path to the OneDrive folder to search, or its unique identifier
+        /// prefixed with "id:"
+        path: String,
+        #[clap(short = 't', long = "type", possible_values = TYPE_OPTIONS)]
+        /// Only show items of this kind
+        item_type: Option<ItemType>,
+        #[clap(
+            short,
+            long = "ext",
+            value_name = "EXT",
+            multiple_occurrences = true,
+            use_value_delimiter = true
+        )]
+        /// Only show files with one of these extensions, ie: "pdf,docx"
+        extensions: Vec<String>,
+        #[clap(long, value_name = "DATE", parse(try_from_str = parse_date))]
+        /// Only show items modified on or after this date, ie: "2022-06-18"
+        modified_after: Option<DateTime<Utc>>,
+        #[clap(long, value_name = "DATE", parse(try_from_str = parse_date))]
+        /// Only show items modified before this date, ie: "2022-06-18"
+        modified_before: Option<DateTime<Utc>>,
+    },
+    /// Find files and folders below a OneDrive folder which meet all of the
+    /// given criteria
+    ///
+    /// Like the predicates of the 'find' command, options may also be given
+    /// with a single dash, ie: find /Photos -name '*.heic' -size +10M
+    Find {
+        #[clap(default_value = "/")]
+        /// Path to the OneDrive folder to walk, or its unique identifier
+        /// prefixed with "id:"
+        path: String,
+        #[clap(long, value_name = "PATTERN", conflicts_with = "iname")]
+        /// Only show items whose names match this wildcard pattern
+        name: Option<Pattern>,
+        #[clap(long, value_name = "PATTERN")]
+        /// Like --name, but ignoring differences in case
+        iname: Option<Pattern>,
+        #[clap(long = "type", possible_values = FIND_TYPE_OPTIONS)]
+        /// Only show items of this kind
+        item_type: Option<ItemType>,
+        #[clap(long, value_name = "[+-]N[ckMGT]", allow_hyphen_values = true)]
+        /// Only show items larger than (+), smaller than (-) or exactly this
+        /// size, in bytes unless a unit is given. Sizes are rounded up to
+        /// whole units, ie: -1M only matches empty files
+        size: Option<SizeThreshold>,
+        #[clap(long, value_name = "[+-]DAYS", allow_hyphen_values = true)]
+        /// Only show items last modified more than (+), less than (-) or
+        /// exactly this many whole days ago
+        mtime: Option<Threshold>,
+        #[clap(long, value_name = "LEVELS")]
+        /// Deepest level of subfolders to walk, with 0 only checking the
+        /// folder itself
+        maxdepth: Option<usize>,
+        #[clap(long)]
+        /// Write the path of each item followed by a null character, for
+        /// use with 'xargs -0', instead of using the output format
+        print0: bool,
+        #[clap(
+            long,
+            value_name = "SUBCOMMAND",
+            multiple_values = true,
+            allow_hyphen_values = true,
+            value_terminator = EXEC_TERMINATOR
+        )]
+        /// Run another subcommand of this app for each item, terminated by
+        /// ';', with '{}' replaced by the path of the item, ie:
+        /// -exec rm -y {} ';'. Stops at the first subcommand that fails
+        exec: Option<Vec<String>>,
+    },
+    /// Show the storage space used by and available to your OneDrive
+    #[clap(alias = "quota")]
+    Df {
+        #[clap(short, long)]
+        /// Show exact sizes in bytes rather than in human readable form
+        bytes: bool,
+        #[clap(long, value_name = "PERCENT")]
+        /// Fail with a non-zero exit code once this percentage of the
+        /// drive has been used
+        warn_at: Option<f64>,
+    },
+}
+
+/// Runs the handler for the subcommand selected by the user
+///
+/// # Arguments
+///
+/// * `cmd` - Subcommand to run, along with its options
+/// * `output` - Destination for the results of the command
+async fn dispatch(cmd: SubCommand, output: Output) -> MyResult<()> {
+    match cmd {
+        // There is nothing to connect to until the app has been initialized
+        SubCommand::Init { browser } => init_cmd(browser, output).await,
+        cmd => run_cmd(&connect().await?, cmd, output).await,
+    }
+}
+
+/// Runs the handler for a subcommand that works with the content of OneDrive
+///
+/// # Arguments
+///
+/// * `service` - Client used to communicate with OneDrive, shared by every
+///               subcommand run by the 'find' subcommand
+/// * `cmd` - Subcommand to run, along with its options
+/// * `output` - Destination for the results of the command
+async fn run_cmd(service: &OneDrive, cmd: SubCommand, output: Output) -> MyResult<()> {
+    match cmd {
+        SubCommand::Init { .. } => {
+            Err(SimpleError::new("The init subcommand can not be run by find").into())
+        }
+        SubCommand::Ls {
+            path,
+            long,
+            human,
+            all,
+            sort,
+            recursive,
+        } => {
+            ls_cmd(
+                service,
+                &path,
+                long,
+                all,
+                sort,
+                recursive,
+                output.human(human),
+            )
+            .await
+        }
+        SubCommand::Upload {
+            source,
+            target,
+            sourcefile,
+            destination,
+            conflict,
+        } => {
+            // Clap ensures one of the source arguments has been provided
+            let source = source.or(sourcefile).unwrap_or_default();
+            let destination = target.or(destination);
+            let destination = destination.as_deref().unwrap_or("/");
+            upload_cmd(service, &source, destination, conflict, output).await
+        }
+        SubCommand::Mkdir {
+            path,
+            parents,
+            conflict,
+        } => mkdir_cmd(service, &path, parents, conflict, output).await,
+        SubCommand::Mv {
+            source,
+            destination,
+            conflict,
+        } => mv_cmd(service, &source, &destination, conflict, output).await,
+        SubCommand::Cp {
+            source,
+            destination,
+            no_wait,
+            conflict,
+        } => cp_cmd(service, &source, &destination, no_wait, conflict, output).await,
+        SubCommand::Rm {
+            path,
+            recursive,
+            yes,
+            dry_run,
+            permanent,
+        } => rm_cmd(service, &path, recursive, yes, dry_run, permanent, output).await,
+        SubCommand::Cat { paths, range } => cat_cmd(service, &paths, range).await,
+        SubCommand::Stat { path } => stat_cmd(service, &path, output).await,
+        SubCommand::Me => me_cmd(service, output).await,
+        SubCommand::Du {
+            path,
+            depth,
+            human,
+            sort,
+        } => du_cmd(service, &path, depth, sort, output.human(human)).await,
+        SubCommand::Tree {
+            path,
+            level,
+            dirs_only,
+            all,
+            size,
+            human,
+        } => {
+            tree_cmd(
+                service,
+                &path,
+                level,
+                dirs_only,
+                all,
+                size,
+                output.human(human),
+            )
+            .await
+        }
+        SubCommand::Search {
+            query,
+            path,
+            item_type,
+            extensions,
+            modified_after,
+            modified_before,
+        } => {
+            let filter = ItemFilter {
+                item_type,
+                extensions,
+                modified_after,
+                modified_before,
+                ..ItemFilter::default()
+            };
+            search_cmd(service, &query, &path, &filter, output).await
+        }
+        SubCommand::Find {
+            path,
+            name,
+            iname,
+            item_type,
+            size,
+            mtime,
+            maxdepth,
+            print0,
+            exec,
+        } => {
+            let filter = ItemFilter {
+                item_type,
+                ignore_case: iname.is_some(),
+                name: name.or(iname),
+                size,
+                modified_days: mtime,
+                ..ItemFilter::default()
+            };
+            match exec {
+                Some(template) => {
+                    for item in find_items(service, &path, &filter, maxdepth).await? {
+                        exec_cmd(service, &template, item.path()).await?;
+                    }
+                    Ok(())
+                }
+                None => find_cmd(service, &path, &filter, maxdepth, print0, output).await,
+            }
+        }
+        SubCommand::Df { bytes, warn_at } => df_cmd(service, warn_at, output.human(!bytes)).await,
+    }
+}
+
+/// Runs a subcommand for an item found by the 'find' subcommand
+///
+/// # Arguments
+///
+/// * `service` - Client used to communicate with OneDrive
+/// * `template` - Arguments for the subcommand, in which each '{}' is
+///                replaced by the path to the item. Results are reported in
+///                the output format selected by these arguments
+/// * `path` - Path to the item
+async fn exec_cmd(service: &OneDrive, template: &[String], path: &str) -> MyResult<()> {
+    let args = template.iter().map(|a| a.replace(EXEC_PLACEHOLDER, path));
+    let name = env!("CARGO_PKG_NAME").to_string();
+    let parsed = Args::try_parse_from(find_style_args(std::iter::once(name).chain(args)))?;
+    Box::pin(run_cmd(service, parsed.cmd, Output::new(parsed.output))).await
+}
+
+/// Converts options of the 'find' subcommand given with a single dash, like
+/// the predicates of the 'find' command, to the form expected by our parser
+/// ie: "-name" becomes "--name". The arguments of the subcommand run with
+/// "-exec" are left untouched
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments, starting with the name of the app
+fn find_style_args<I: IntoIterator<Item = String>>(args: I) -> Vec<String> {
+    let mut args = args.into_iter();
+    let mut retval: Vec<String> = args.next().into_iter().collect();
+
+    // Skip ahead to the subcommand, past any global options
+    for arg in args.by_ref() {
+        let is_option_value = matches!(retval.last().map(String::as_str), Some("-o" | "--output"));
+        let is_subcommand = !arg.starts_with('-') && !is_option_value;
+        retval.push(arg);
+        if is_subcommand {
+            break;
+        }
+    }
+    if retval.last().map(String::as_str) != Some("find") {
+        retval.extend(args);
+        return retval;
+    }
+
+    let mut in_exec = false;
+    for arg in args {
+        match arg.strip_prefix('-') {
+            _ if in_exec => in_exec = arg != EXEC_TERMINATOR,
+            Some(name) if FIND_PREDICATES.contains(&name) => {
+                in_exec = name == "exec";
+                retval.push(format!("--{}", name));
+                continue;
+            }
+            _ => {}
+        }
+        retval.push(arg);
+    }
+    retval
+}
+
+/// Entrypoint function for our command line interface
+/// Errors are reported to the user in the selected output format, and
+/// the exit code for the app is returned
+pub async fn run() -> ExitCode {
+    let args = Args::parse_from(find_style_args(env::args()));
+    let output = Output::new(args.output);
+    match dispatch(args.cmd, output).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            output.print_error(e.as_ref());
+            ExitCode::FAILURE
+        }
+    }
+}
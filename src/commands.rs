@@ -1,17 +1,40 @@
 //! Entrypoint functions for all of our CLI commands
-use crate::api::onedrive::OneDrive as odapi;
+use crate::api::driveitem::{ByteRange, ConflictBehavior, DriveItem, ListOptions};
+use crate::api::is_not_found;
+use crate::api::onedrive::{OneDrive as odapi, DEFAULT_API_URL};
 use crate::auth::{
     get_auth_data, get_auth_url, get_oauth_token_from_browser, parse_token, refresh_auth_data,
-    REDIRECT_URI,
+    DEFAULT_AUTH_URL, REDIRECT_URI,
 };
 use crate::configfile::Configuration;
-use onedrive_api::{DriveLocation, ItemLocation, OneDrive};
+use crate::filter::ItemFilter;
+use crate::output::{
+    Action, ActionResult, DriveInfo, FoundItem, ItemInfo, Output, OutputFormat, StatInfo, TreeInfo,
+    TreeNode, UsageInfo, UserInfo,
+};
+use crate::paths::{join_path, split_path};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::{future, FutureExt, TryStreamExt};
+use simple_error::SimpleError;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::env;
 use std::error::Error;
-use std::fs::File;
-use std::io::{stdin, stdout, Read, Write};
+use std::io::{stderr, stdin, stdout, ErrorKind, Write};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tempfile::tempfile;
+use tokio::fs::File;
+use tokio::io::AsyncSeekExt;
+
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+/// Environment variable that overrides the base URL of the Graph REST API
+const API_URL_VAR: &str = "ONEDRIVE_API_URL";
+/// Environment variable that overrides the base URL of the OAuth service
+const AUTH_URL_VAR: &str = "ONEDRIVE_AUTH_URL";
 
 /// Path to folder containing configuration data for the app
 fn config_folder() -> PathBuf {
@@ -29,6 +52,42 @@ fn config_file() -> PathBuf {
     config_folder().join("config.yml")
 }
 
+/// Determines which URL to use for a remote service. URLs provided by an
+/// environment variable take precedence over those in the configuration
+/// file, and the official Microsoft services are used if neither are set
+///
+/// # Arguments
+///
+/// * `env_var` - Name of the environment variable that overrides the URL
+/// * `configured` - URL loaded from the configuration file, if any
+/// * `default` - URL to use when no override has been provided
+fn service_url(env_var: &str, configured: Option<&String>, default: &str) -> String {
+    match env::var(env_var) {
+        Ok(url) if !url.is_empty() => url,
+        _ => configured.map_or(default, |u| u.as_str()).to_string(),
+    }
+}
+
+/// Gets the base URL of the Graph REST API to connect to
+///
+/// # Arguments
+///
+/// * `config` - Options loaded from the app configuration file, if any
+fn api_url(config: Option<&Configuration>) -> String {
+    let configured = config.and_then(|c| c.api_url.as_ref());
+    service_url(API_URL_VAR, configured, DEFAULT_API_URL)
+}
+
+/// Gets the base URL of the OAuth service to authenticate with
+///
+/// # Arguments
+///
+/// * `config` - Options loaded from the app configuration file, if any
+fn auth_url(config: Option<&Configuration>) -> String {
+    let configured = config.and_then(|c| c.auth_url.as_ref());
+    service_url(AUTH_URL_VAR, configured, DEFAULT_AUTH_URL)
+}
+
 /// Entry point function for the "init" subcommand
 ///
 /// The command prompts the user for authentication parameters to OneDrive
@@ -41,19 +100,24 @@ fn config_file() -> PathBuf {
 /// * `browser` - True if the user wants the browser to be automatically
 ///               launched by our app, and have the response from the
 ///               authentication request automatically intercepted
-pub fn init_cmd(browser: bool) -> MyResult<()> {
+/// * `output` - Destination for the results of the command
+pub async fn init_cmd(browser: bool, output: Output) -> MyResult<()> {
+    // Custom service URLs from any previous configuration are preserved
+    let previous = Configuration::from_file(&config_file()).ok();
+    let auth_url = auth_url(previous.as_ref());
+
     let response_url = match browser {
         true => {
-            println!("Waiting for OneDrive authentication request in your browser...");
-            println!("Reference URL: {}", get_auth_url());
-            println!("Listening for response on: {}", REDIRECT_URI);
+            eprintln!("Waiting for OneDrive authentication request in your browser...");
+            eprintln!("Reference URL: {}", get_auth_url(&auth_url));
+            eprintln!("Listening for response on: {}", REDIRECT_URI);
 
-            get_oauth_token_from_browser()?
+            get_oauth_token_from_browser(&auth_url).await?
         }
         false => {
-            println!("Open this URL in your browser: {}", get_auth_url());
-            print!("Paste the response URL here: ");
-            stdout().flush()?;
+            eprintln!("Open this URL in your browser: {}", get_auth_url(&auth_url));
+            eprint!("Paste the response URL here: ");
+            stderr().flush()?;
             let mut temp = String::new();
             stdin().read_line(&mut temp)?;
             temp
@@ -61,114 +125,936 @@ pub fn init_cmd(browser: bool) -> MyResult<()> {
     };
 
     let token = parse_token(&response_url)?;
-    let auth = get_auth_data(&token)?;
+    let auth = get_auth_data(&auth_url, &token).await?;
 
     let config = Configuration {
         auth_token: auth.access_token,
         refresh_token: auth.refresh_token,
+        api_url: previous.as_ref().and_then(|c| c.api_url.clone()),
+        auth_url: previous.and_then(|c| c.auth_url),
     };
 
     config.save(&config_file())?;
 
-    Ok(())
+    let path = config_file().to_string_lossy().to_string();
+    output.print(&ActionResult::new(Action::Initialized, &path))
 }
 
-/// Command handler for the "Me" subcommand of our app
-/// Displays profile information for the currently logged in user
-pub async fn me_cmd() -> MyResult<()> {
+/// Creates a client for the OneDrive API using the authentication tokens
+/// stored in our configuration file. If the tokens have expired they are
+/// renewed, and the configuration file is updated accordingly
+pub async fn connect() -> MyResult<odapi> {
     let mut config = Configuration::from_file(&config_file())?;
+    let api_url = api_url(Some(&config));
 
-    let service = odapi::new(&config.auth_token);
-    let result = service.me().await;
-    let me = match result {
-        Ok(d) => d,
-        Err(_) => {
-            let temp = refresh_auth_data(&config.refresh_token)?;
-            config.auth_token = temp.access_token;
-            config.refresh_token = temp.refresh_token;
-            config.save(&config_file())?;
+    let service = odapi::builder(&config.auth_token)
+        .base_url(&api_url)
+        .build();
+    if service.me().await.is_ok() {
+        return Ok(service);
+    }
 
-            let service = odapi::new(&config.auth_token);
-            service.me().await?
-        }
+    // If our first attempt to perform the operation fails, request a token
+    // refresh from OneDrive and try again
+    let temp = refresh_auth_data(&auth_url(Some(&config)), &config.refresh_token).await?;
+    config.auth_token = temp.access_token;
+    config.refresh_token = temp.refresh_token;
+    config.save(&config_file())?;
+
+    Ok(odapi::builder(&config.auth_token)
+        .base_url(&api_url)
+        .build())
+}
+
+/// Time to wait between checks on the progress of a copy operation
+const COPY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Prefix identifying command line arguments that refer to an item by its
+/// unique identifier rather than by its path, ie: "id:ABC123!101"
+const ID_PREFIX: &str = "id:";
+
+/// Loads a file or folder referred to on the command line, either by its
+/// path or by its unique identifier when prefixed with "id:"
+///
+/// # Arguments
+///
+/// * `service` - Client used to communicate with OneDrive
+/// * `item` - Path or prefixed identifier of the item to load
+async fn find_item(service: &odapi, item: &str) -> MyResult<DriveItem> {
+    match item.strip_prefix(ID_PREFIX) {
+        Some(id) => service.item_by_id(id).await,
+        None => service.item_by_path(item).await,
+    }
+}
+
+/// Command handler for the "Me" subcommand of our app
+/// Displays profile information for the currently logged in user, along
+/// with details of their default drive
+///
+/// # Arguments
+///
+/// * `service` - Client used to communicate with OneDrive
+/// * `output` - Destination for the results of the command
+pub async fn me_cmd(service: &odapi, output: Output) -> MyResult<()> {
+    let me = service.me().await?;
+
+    // Not every account has a OneDrive, so the drive details are optional
+    let drive = match me.drive().await {
+        Ok(drive) => Some(drive),
+        Err(e) if is_not_found(e.as_ref()) => None,
+        Err(e) => return Err(e),
     };
-    println!("{:#?}", me);
+    output.print(&UserInfo::new(&me, drive.as_ref()))
+}
 
-    Ok(())
+/// Order in which the 'ls' subcommand shows the contents of a folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Alphabetical order, ignoring case
+    Name,
+    /// Largest items first
+    Size,
+    /// Most recently modified items first
+    Time,
 }
 
-/// Entrypoint method for the 'ls' subcommand
-/// Shows a directory listing of the root OneDrive folder
-pub async fn ls_cmd() -> MyResult<()> {
-    let mut config = Configuration::from_file(&config_file())?;
+impl FromStr for SortKey {
+    type Err = SimpleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "name" => Ok(SortKey::Name),
+            "size" => Ok(SortKey::Size),
+            "time" => Ok(SortKey::Time),
+            _ => Err(SimpleError::new(format!(
+                "Unsupported sort order {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Sorts a list of drive items in place
+///
+/// # Arguments
+///
+/// * `items` - Items to sort
+/// * `key` - Property to sort the items by
+fn sort_items(items: &mut [DriveItem], key: SortKey) {
+    items.sort_by(|a, b| {
+        let by_name = a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        match key {
+            SortKey::Name => by_name,
+            SortKey::Size => b.size.cmp(&a.size).then(by_name),
+            SortKey::Time => b
+                .last_modified_date_time
+                .cmp(&a.last_modified_date_time)
+                .then(by_name),
+        }
+    });
+}
 
-    let service = odapi::new(&config.auth_token);
-    let result = service.me().await;
-    let me = match result {
-        Ok(d) => d,
-        Err(_) => {
-            let temp = refresh_auth_data(&config.refresh_token)?;
-            config.auth_token = temp.access_token;
-            config.refresh_token = temp.refresh_token;
-            config.save(&config_file())?;
+/// Checks whether an item is hidden from directory listings by default.
+/// This includes items whose names start with a "." and package items
+/// such as OneNote notebooks, which are managed by other applications
+///
+/// # Arguments
+///
+/// * `item` - Item to check
+fn is_hidden(item: &DriveItem) -> bool {
+    item.name.starts_with('.') || item.package.is_some()
+}
 
-            let service = odapi::new(&config.auth_token);
-            service.me().await?
+/// Entrypoint method for the 'ls' subcommand
+/// Shows a directory listing of a OneDrive folder
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the folder to list, or its identifier prefixed with "id:"
+/// * `long` - true to show detailed information about each item
+/// * `all` - true to include hidden and package items in the listing
+/// * `sort` - order in which to show the items in each folder
+/// * `recursive` - true to list the contents of all subfolders as well
+/// * `output` - destination for the results of the command
+pub async fn ls_cmd(
+    service: &odapi,
+    path: &str,
+    long: bool,
+    all: bool,
+    sort: SortKey,
+    recursive: bool,
+    output: Output,
+) -> MyResult<()> {
+    // Plain output shows each folder separately, like the 'ls' command,
+    // whereas all other formats produce a single list of items
+    let print_items = |items: &[ItemInfo]| -> MyResult<()> {
+        match long {
+            true => output.print_table(items, false),
+            false => output.print_all(items)?,
         }
+        Ok(())
     };
-    let root = me.root().await?;
-    let children = root.children().await?;
+    let plain = output.format == OutputFormat::Plain;
+
+    let item = find_item(service, path).await?;
+    if !item.is_folder() {
+        let items = [ItemInfo::from(&item)];
+        match plain {
+            true => print_items(&items)?,
+            false => output.print_all(&items)?,
+        }
+        return Ok(());
+    }
 
-    // Iterate through children and show their names to the user
-    for i in children {
-        println!("{}", i.name);
+    // Folders are listed depth first, in the same order their names appear
+    // in the listing of their parent. Paths are tracked as we go, since
+    // OneDrive does not always provide the path of the parent folder
+    let location = item.path().unwrap_or_else(|| path.to_string());
+    let mut pending = vec![(location, item)];
+    let mut first = true;
+    let mut records = Vec::new();
+    while let Some((location, folder)) = pending.pop() {
+        let mut children: Vec<DriveItem> = folder
+            .children(&ListOptions::default())
+            .try_filter(|i| future::ready(all || !is_hidden(i)))
+            .try_collect()
+            .await?;
+        sort_items(&mut children, sort);
+        let items: Vec<ItemInfo> = children.iter().map(ItemInfo::from).collect();
+
+        if !plain {
+            records.extend(items);
+        } else {
+            if recursive {
+                if !first {
+                    println!();
+                }
+                println!("{}:", location);
+            }
+            first = false;
+            print_items(&items)?;
+        }
+
+        if recursive {
+            pending.extend(
+                children
+                    .into_iter()
+                    .filter(|i| i.is_folder())
+                    .rev()
+                    .map(|child| (join_path(&location, &child.name), child)),
+            );
+        }
     }
-    Ok(())
+    match plain {
+        true => Ok(()),
+        false => output.print_all(&records),
+    }
+}
+
+/// Source path which refers to stdin rather than a local file
+const STDIN_PATH: &str = "-";
+
+/// Copies everything piped to stdin into an anonymous temporary file, so it
+/// can be uploaded once its size is known. OneDrive upload sessions need the
+/// total size of a file, and buffering on disk keeps large uploads out of
+/// memory. Stdin is read without blocking the async runtime
+/// Returns the temporary file, positioned at its start, along with its size
+async fn spool_stdin() -> MyResult<(File, u64)> {
+    let mut spool = File::from_std(tempfile()?);
+    let file_size = tokio::io::copy(&mut tokio::io::stdin(), &mut spool).await?;
+    spool.rewind().await?;
+    Ok((spool, file_size))
 }
 
 /// Entrypoint function that uploads a new file to OneDrive
-/// Currently assumes target folder is going to be the OneDrive
-/// root foldeer
 ///
 /// # Arguments
 ///
-/// * `source_file` - path to the local file to upload
-pub async fn upload_cmd(source_file: &PathBuf) -> MyResult<()> {
-    let mut config = Configuration::from_file(&config_file())?;
-    let client = reqwest::Client::new();
-
-    let service = OneDrive::new(config.auth_token, DriveLocation::me());
-    let src_item = ItemLocation::root();
-    let result = service.new_upload_session(src_item).await;
-    let (session, _meta) = match result {
-        Ok((s, m)) => (s, m),
-        Err(_) => {
-            // If our first attempt to perform the operation fails, request a token
-            // refresh from OneDrive and try again
-            let temp = refresh_auth_data(&config.refresh_token)?;
-            config.auth_token = temp.access_token;
-            config.refresh_token = temp.refresh_token;
-            config.save(&config_file())?;
-
-            let service = OneDrive::new(config.auth_token, DriveLocation::me());
-            let src_item = ItemLocation::root();
-            service.new_upload_session(src_item).await?
+/// * `service` - client used to communicate with OneDrive
+/// * `source_file` - path to the local file to upload, or "-" to upload
+///                   the content of stdin
+/// * `destination` - path to an existing OneDrive folder to upload the file
+///                   into, or the path of the new file
+/// * `conflict` - how to handle an existing file with the same name
+/// * `output` - destination for the results of the command
+pub async fn upload_cmd(
+    service: &odapi,
+    source_file: &PathBuf,
+    destination: &str,
+    conflict: ConflictBehavior,
+    output: Output,
+) -> MyResult<()> {
+    // The content of stdin has no name, and its size is only known once
+    // all of it has been read
+    let source = match source_file.as_os_str() == STDIN_PATH {
+        true => None,
+        false => {
+            let file_name = source_file
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| SimpleError::new("Source path does not refer to a file"))?;
+            let file = File::open(source_file).await?;
+            Some((file_name, file))
+        }
+    };
+
+    let file_name = source.as_ref().map(|(n, _)| *n);
+    let (folder, name) = find_destination(service, destination, file_name, None).await?;
+    let (mut file, file_size) = match source {
+        Some((_, file)) => {
+            let file_size = file.metadata().await?.len();
+            (file, file_size)
         }
+        None => spool_stdin().await?,
     };
-    let mut file = File::open(source_file)?;
-    let file_size = file.metadata()?.len();
-    let mut buffer = vec![0; file_size as usize];
-    file.read_exact(&mut buffer).expect("buffer overflow");
-    let dest_item = session
-        .upload_part(buffer, 0..file_size, file_size, &client)
+    let item = folder
+        .upload_from(&name, &mut file, file_size, conflict)
         .await?;
-    match dest_item {
-        Some(item) => {
-            println!("Successfully uploaded {}", item.name.unwrap());
+    let path = item
+        .path()
+        .unwrap_or_else(|| join_path(destination, &item.name));
+    output.print(&ActionResult::with_item(Action::Uploaded, &path, &item))
+}
+
+/// Entrypoint function for the 'mkdir' subcommand
+/// Creates a new folder on OneDrive
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the OneDrive folder to create
+/// * `parents` - true to create any missing parent folders, and to succeed
+///               without changes if the folder already exists
+/// * `conflict` - how to handle an existing item with the same name
+/// * `output` - destination for the results of the command
+pub async fn mkdir_cmd(
+    service: &odapi,
+    path: &str,
+    parents: bool,
+    conflict: ConflictBehavior,
+    output: Output,
+) -> MyResult<()> {
+    let (parent_path, name) = split_path(path)?;
+
+    if !parents {
+        let parent = service.item_by_path(parent_path).await?;
+        let folder = parent.create_folder(name, conflict).await?;
+        let created = folder.path().unwrap_or_else(|| path.to_string());
+        return output.print(&ActionResult::with_item(Action::Created, &created, &folder));
+    }
+
+    let mut folder = service.item_by_path("/").await?;
+    let mut current_path = String::new();
+    let mut results = Vec::new();
+    for name in path.split('/').filter(|s| !s.is_empty()) {
+        current_path = format!("{}/{}", current_path, name);
+        folder = match folder.child(name).await {
+            Ok(item) if item.is_folder() => item,
+            Ok(_) => {
+                let msg = format!("{} already exists and is not a folder", current_path);
+                return Err(SimpleError::new(msg).into());
+            }
+            Err(e) if is_not_found(e.as_ref()) => {
+                let item = folder.create_folder(name, conflict).await?;
+                results.push(ActionResult::with_item(
+                    Action::Created,
+                    &current_path,
+                    &item,
+                ));
+                item
+            }
+            Err(e) => return Err(e),
+        };
+    }
+    output.print_all(&results)
+}
+
+/// Asks the user a yes / no question on the console
+/// Returns true if the user answered yes
+///
+/// # Arguments
+///
+/// * `question` - Prompt to show to the user
+fn confirm(question: &str) -> MyResult<bool> {
+    eprint!("{} [y/N] ", question);
+    stderr().flush()?;
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Entrypoint function for the 'rm' subcommand
+/// Deletes a file or folder from OneDrive
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the OneDrive file or folder to delete
+/// * `recursive` - true to allow folders to be deleted along with all of
+///                 their contents
+/// * `yes` - true to delete folders without asking the user to confirm
+/// * `dry_run` - true to report what would be deleted without deleting it
+/// * `permanent` - true to bypass the recycle bin
+/// * `output` - destination for the results of the command
+pub async fn rm_cmd(
+    service: &odapi,
+    path: &str,
+    recursive: bool,
+    yes: bool,
+    dry_run: bool,
+    permanent: bool,
+    output: Output,
+) -> MyResult<()> {
+    let item = service.item_by_path(path).await?;
+    let item_path = item.path().unwrap_or_else(|| path.to_string());
+
+    if item.is_root() {
+        return Err(SimpleError::new("Refusing to delete the root folder").into());
+    }
+    if item.is_folder() && !recursive {
+        let msg = format!("{} is a folder, use -r to delete it", item_path);
+        return Err(SimpleError::new(msg).into());
+    }
+    // Personal accounts have no permanent delete operation, so we fail early
+    // rather than leaving the user to decipher the error from the service
+    let drive_type = item
+        .parent_reference
+        .as_ref()
+        .and_then(|r| r.drive_type.as_deref());
+    if permanent && drive_type == Some("personal") {
+        let msg = "Permanent delete is not supported by personal OneDrive accounts";
+        return Err(SimpleError::new(msg).into());
+    }
+
+    let mut result = ActionResult::with_item(Action::Deleted, &item_path, &item);
+    result.permanent = permanent;
+    if dry_run {
+        result.dry_run = true;
+        return output.print(&result);
+    }
+    if item.is_folder() && !yes {
+        let description = match item.folder.as_ref().and_then(|f| f.child_count) {
+            Some(count) => format!("{} and the {} item(s) it contains", item_path, count),
+            None => item_path.clone(),
+        };
+        if !confirm(&format!("Delete {}?", description))? {
+            return output.print(&ActionResult::new(Action::Cancelled, &item_path));
         }
-        None => {
-            println!("Failed to upload file");
+    }
+
+    match permanent {
+        true => item.permanent_delete().await?,
+        false => item.delete().await?,
+    }
+    output.print(&result)
+}
+
+/// Determines where an item should be moved, copied or uploaded to. Like
+/// the Unix 'mv' and 'cp' commands, a destination referring to an existing
+/// folder places the item inside that folder while keeping its current name
+/// Returns the folder to place the item in, and the new name of the item
+///
+/// # Arguments
+///
+/// * `service` - Client used to communicate with OneDrive
+/// * `destination` - Path to an existing folder, or the new path of the item
+/// * `name` - Current name of the item, if it has one
+/// * `id` - Identifier of the item, if it is already stored on OneDrive
+async fn find_destination(
+    service: &odapi,
+    destination: &str,
+    name: Option<&str>,
+    id: Option<&str>,
+) -> MyResult<(DriveItem, String)> {
+    let existing = match service.item_by_path(destination).await {
+        Ok(existing) => Some(existing),
+        Err(e) if is_not_found(e.as_ref()) => None,
+        Err(e) => return Err(e),
+    };
+    match existing {
+        Some(folder) if folder.is_folder() && Some(folder.id.as_str()) != id => match name {
+            Some(name) => Ok((folder, name.to_string())),
+            None => {
+                let msg = format!(
+                    "{} is a folder, so a name is needed for the new file",
+                    destination
+                );
+                Err(SimpleError::new(msg).into())
+            }
+        },
+        _ => {
+            let (parent_path, name) = split_path(destination)?;
+            Ok((service.item_by_path(parent_path).await?, name.to_string()))
+        }
+    }
+}
+
+/// Entrypoint function for the 'mv' subcommand
+/// Moves a file or folder to another location on OneDrive, optionally
+/// renaming it along the way
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `source` - path to the item to move, or its identifier prefixed with "id:"
+/// * `destination` - path to an existing folder to move the item into, or
+///                   the new path for the item
+/// * `conflict` - how to handle an existing item with the same name
+/// * `output` - destination for the results of the command
+pub async fn mv_cmd(
+    service: &odapi,
+    source: &str,
+    destination: &str,
+    conflict: ConflictBehavior,
+    output: Output,
+) -> MyResult<()> {
+    let item = find_item(service, source).await?;
+    if item.is_root() {
+        return Err(SimpleError::new("Refusing to move the root folder").into());
+    }
+
+    let (parent, name) =
+        find_destination(service, destination, Some(&item.name), Some(&item.id)).await?;
+    let old_path = item.path().unwrap_or_else(|| source.to_string());
+    let moved = item.move_to(&parent, &name, conflict).await?;
+    let mut result = ActionResult::with_item(Action::Moved, &old_path, &moved);
+    result.destination = Some(moved.path().unwrap_or_else(|| destination.to_string()));
+    output.print(&result)
+}
+
+/// Entrypoint function for the 'cp' subcommand
+/// Copies a file or folder to another location on OneDrive. The copy is
+/// performed by the OneDrive service, so no content is transferred locally
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `source` - path to the item to copy, or its identifier prefixed with "id:"
+/// * `destination` - path to an existing folder to copy the item into, or
+///                   the path of the new copy
+/// * `no_wait` - true to return as soon as the copy has started, rather
+///               than waiting for it to complete
+/// * `conflict` - how to handle an existing item with the same name
+/// * `output` - destination for the results of the command
+pub async fn cp_cmd(
+    service: &odapi,
+    source: &str,
+    destination: &str,
+    no_wait: bool,
+    conflict: ConflictBehavior,
+    output: Output,
+) -> MyResult<()> {
+    let item = find_item(service, source).await?;
+    if item.is_root() {
+        return Err(SimpleError::new("Refusing to copy the root folder").into());
+    }
+    let (parent, name) =
+        find_destination(service, destination, Some(&item.name), Some(&item.id)).await?;
+    let job = item.copy_to(&parent, &name, conflict).await?;
+
+    let source_path = item.path().unwrap_or_else(|| source.to_string());
+    let mut result = ActionResult::new(Action::Copied, &source_path);
+    result.destination = Some(join_path(&parent.path().unwrap_or_default(), &name));
+    if no_wait {
+        result.action = Action::CopyStarted;
+        result.monitor_url = Some(job.monitor_url);
+        return output.print(&result);
+    }
+
+    let status = job
+        .wait(COPY_POLL_INTERVAL, |s| {
+            eprint!(
+                "\rCopying {}: {:.0}% complete",
+                source_path, s.percentage_complete
+            );
+            stderr().flush().ok();
+        })
+        .await;
+    eprintln!();
+    status?;
+    output.print(&result)
+}
+
+/// Entrypoint function for the 'df' subcommand
+/// Shows how much storage space is used by and available to the default
+/// drive of the current user
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `warn_at` - percentage of the drive that may be used before the
+///               command fails, allowing scheduled jobs to raise alerts
+/// * `output` - destination for the results of the command
+pub async fn df_cmd(service: &odapi, warn_at: Option<f64>, output: Output) -> MyResult<()> {
+    if let Some(threshold) = warn_at {
+        if !(0.0..=100.0).contains(&threshold) {
+            let msg = format!("Warning threshold {} must be between 0 and 100", threshold);
+            return Err(SimpleError::new(msg).into());
         }
+    }
+
+    let drive = DriveInfo::from(&service.drive().await?);
+    output.print(&drive)?;
+
+    let threshold = match warn_at {
+        Some(threshold) => threshold,
+        None => return Ok(()),
     };
+    let percent_used = drive
+        .quota
+        .and_then(|q| q.percent_used)
+        .ok_or_else(|| SimpleError::new("OneDrive did not report the quota for the drive"))?;
+    if percent_used >= threshold {
+        let msg = format!(
+            "Drive usage of {:.1}% has reached the warning threshold of {}%",
+            percent_used, threshold
+        );
+        return Err(SimpleError::new(msg).into());
+    }
     Ok(())
 }
+
+/// Determines the storage space used by a folder, recording the usage of
+/// the folder and its subfolders down to a maximum depth. Sizes reported by
+/// OneDrive are used where available, otherwise the sizes of all the files
+/// within the folder are added up
+/// Returns the size of the folder in bytes
+///
+/// # Arguments
+///
+/// * `folder` - Folder to measure
+/// * `depth` - Number of levels the folder is below the folder being reported on
+/// * `max_depth` - Deepest level of subfolders to record the usage of
+/// * `records` - Usage of each folder, with subfolders before their parents
+fn folder_usage<'a>(
+    folder: &'a DriveItem,
+    depth: usize,
+    max_depth: usize,
+    records: &'a mut Vec<UsageInfo>,
+) -> BoxFuture<'a, MyResult<u64>> {
+    async move {
+        let path = folder.path().unwrap_or_else(|| folder.name.clone());
+        if let (Some(size), true) = (folder.size, depth >= max_depth) {
+            if depth == max_depth {
+                records.push(UsageInfo { path, size });
+            }
+            return Ok(size);
+        }
+
+        let mut children = folder.children(&ListOptions::default());
+        let mut total = 0;
+        while let Some(child) = children.try_next().await? {
+            total += match child.is_folder() {
+                true => folder_usage(&child, depth + 1, max_depth, records).await?,
+                false => child.size.unwrap_or_default(),
+            };
+        }
+        let size = folder.size.unwrap_or(total);
+        if depth <= max_depth {
+            records.push(UsageInfo { path, size });
+        }
+        Ok(size)
+    }
+    .boxed()
+}
+
+/// Entrypoint function for the 'du' subcommand
+/// Shows the storage space used by a folder and its subfolders
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the folder to report on, or its identifier prefixed
+///            with "id:"
+/// * `depth` - deepest level of subfolders to show, with 0 showing only the
+///             folder itself
+/// * `sort` - order in which to show the folders. If not provided,
+///            subfolders are shown before their parents, like the 'du' command
+/// * `output` - destination for the results of the command
+pub async fn du_cmd(
+    service: &odapi,
+    path: &str,
+    depth: usize,
+    sort: Option<SortKey>,
+    output: Output,
+) -> MyResult<()> {
+    let folder = find_item(service, path).await?;
+    if !folder.is_folder() {
+        let msg = format!("{} is not a folder", path);
+        return Err(SimpleError::new(msg).into());
+    }
+
+    let mut records = Vec::new();
+    folder_usage(&folder, 0, depth, &mut records).await?;
+    match sort {
+        Some(SortKey::Size) => records.sort_by_key(|r| Reverse(r.size)),
+        Some(_) => records.sort_by_key(|r| r.path.to_lowercase()),
+        None => {}
+    }
+    output.print_all(&records)
+}
+
+/// Loads the folder hierarchy below a drive item
+///
+/// # Arguments
+///
+/// * `item` - Item at the top of the hierarchy
+/// * `depth` - Number of levels of subfolders to load below the item. If
+///             not provided, the entire hierarchy is loaded
+/// * `dirs_only` - True to leave files out of the hierarchy
+/// * `all` - True to include hidden and package items in the hierarchy
+fn load_tree(
+    item: DriveItem,
+    depth: Option<usize>,
+    dirs_only: bool,
+    all: bool,
+) -> BoxFuture<'static, MyResult<TreeNode>> {
+    async move {
+        let mut node = TreeNode::new(&item);
+        if !item.is_folder() || depth == Some(0) {
+            return Ok(node);
+        }
+
+        let mut children: Vec<DriveItem> = item
+            .children(&ListOptions::default())
+            .try_filter(|i| future::ready(all || !is_hidden(i)))
+            .try_filter(|i| future::ready(!dirs_only || i.is_folder()))
+            .try_collect()
+            .await?;
+        sort_items(&mut children, SortKey::Name);
+
+        let mut nodes = Vec::new();
+        for child in children {
+            nodes.push(load_tree(child, depth.map(|d| d - 1), dirs_only, all).await?);
+        }
+        node.children = Some(nodes);
+        Ok(node)
+    }
+    .boxed()
+}
+
+/// Entrypoint function for the 'tree' subcommand
+/// Shows the hierarchy of files and folders below a OneDrive folder
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the folder to show, or its identifier prefixed with "id:"
+/// * `depth` - number of levels of subfolders to show. If not provided, the
+///             entire hierarchy is shown
+/// * `dirs_only` - true to only show folders
+/// * `all` - true to include hidden and package items
+/// * `sizes` - true to show the size of each item
+/// * `output` - destination for the results of the command
+pub async fn tree_cmd(
+    service: &odapi,
+    path: &str,
+    depth: Option<usize>,
+    dirs_only: bool,
+    all: bool,
+    sizes: bool,
+    output: Output,
+) -> MyResult<()> {
+    let folder = find_item(service, path).await?;
+    let tree = load_tree(folder, depth, dirs_only, all).await?;
+    output.print(&TreeInfo::new(tree, sizes || output.human))
+}
+
+/// Determines the full path to an item found by a search
+///
+/// OneDrive leaves the path of the parent folder out of some search
+/// results, in which case the parent folder is loaded to find its path.
+/// Paths of parent folders are cached, since search results often share
+/// the same parent
+///
+/// # Arguments
+///
+/// * `item` - Item to find the path of
+/// * `folders` - Paths of the parent folders loaded so far, by identifier
+async fn found_path(
+    item: &DriveItem,
+    folders: &mut HashMap<String, Option<String>>,
+) -> MyResult<Option<String>> {
+    if let Some(path) = item.path() {
+        return Ok(Some(path));
+    }
+    let parent_id = match item.parent_reference.as_ref().and_then(|p| p.id.as_ref()) {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    if !folders.contains_key(parent_id) {
+        let parent = item.parent().await?;
+        folders.insert(parent_id.clone(), parent.and_then(|p| p.path()));
+    }
+    Ok(folders[parent_id]
+        .as_ref()
+        .map(|folder| join_path(folder, &item.name)))
+}
+
+/// Entrypoint function for the 'search' subcommand
+/// Searches a OneDrive folder and its subfolders for matching items
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `query` - text to search for in the names, metadata and content of items
+/// * `path` - path to the folder to search, or its identifier prefixed with "id:"
+/// * `filter` - criteria the items found must also meet
+/// * `output` - destination for the results of the command
+pub async fn search_cmd(
+    service: &odapi,
+    query: &str,
+    path: &str,
+    filter: &ItemFilter,
+    output: Output,
+) -> MyResult<()> {
+    let folder = find_item(service, path).await?;
+    if !folder.is_folder() {
+        let msg = format!("{} is not a folder", path);
+        return Err(SimpleError::new(msg).into());
+    }
+
+    let now = Utc::now();
+    let items: Vec<DriveItem> = folder
+        .search(query, &ListOptions::default())
+        .try_filter(|i| future::ready(filter.matches(i, now)))
+        .try_collect()
+        .await?;
+
+    let mut folders = HashMap::new();
+    let mut records = Vec::new();
+    for item in items {
+        let mut info = ItemInfo::from(&item);
+        info.path = found_path(&item, &mut folders).await?;
+        records.push(FoundItem(info));
+    }
+    output.print_all(&records)
+}
+
+/// Walks the hierarchy below a OneDrive folder, finding the items that meet
+/// the given criteria. Items are returned depth first, starting with the
+/// folder itself, with the contents of each folder in alphabetical order
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the folder to walk, or its identifier prefixed with "id:"
+/// * `filter` - criteria the items found must meet
+/// * `max_depth` - deepest level of subfolders to walk, with 0 only checking
+///                 the folder itself. If not provided, all subfolders are walked
+pub async fn find_items(
+    service: &odapi,
+    path: &str,
+    filter: &ItemFilter,
+    max_depth: Option<usize>,
+) -> MyResult<Vec<FoundItem>> {
+    let item = find_item(service, path).await?;
+    let now = Utc::now();
+
+    // Paths are tracked as we go, since OneDrive does not always provide
+    // the path of the parent folder of each item
+    let location = item.path().unwrap_or_else(|| path.to_string());
+    let mut pending = vec![(item, location, 0)];
+    let mut retval = Vec::new();
+    while let Some((item, location, depth)) = pending.pop() {
+        if filter.matches(&item, now) {
+            let mut info = ItemInfo::from(&item);
+            info.path = Some(location.clone());
+            retval.push(FoundItem(info));
+        }
+        if !item.is_folder() || max_depth.is_some_and(|d| depth >= d) {
+            continue;
+        }
+
+        let mut children: Vec<DriveItem> =
+            item.children(&ListOptions::default()).try_collect().await?;
+        sort_items(&mut children, SortKey::Name);
+        pending.extend(children.into_iter().rev().map(|child| {
+            let child_path = join_path(&location, &child.name);
+            (child, child_path, depth + 1)
+        }));
+    }
+    Ok(retval)
+}
+
+/// Entrypoint function for the 'find' subcommand
+/// Shows the items below a OneDrive folder that meet the given criteria
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the folder to walk, or its identifier prefixed with "id:"
+/// * `filter` - criteria the items found must meet
+/// * `max_depth` - deepest level of subfolders to walk. If not provided, all
+///                 subfolders are walked
+/// * `print0` - true to write the path of each item followed by a null
+///              character, for use with 'xargs -0', instead of the
+///              selected output format
+/// * `output` - destination for the results of the command
+pub async fn find_cmd(
+    service: &odapi,
+    path: &str,
+    filter: &ItemFilter,
+    max_depth: Option<usize>,
+    print0: bool,
+    output: Output,
+) -> MyResult<()> {
+    let records = find_items(service, path, filter, max_depth).await?;
+    if !print0 {
+        return output.print_all(&records);
+    }
+    let mut writer = stdout().lock();
+    for record in records {
+        write!(writer, "{}\0", record.path())?;
+    }
+    Ok(writer.flush()?)
+}
+
+/// Entrypoint function for the 'stat' subcommand
+/// Shows all of the metadata describing a single file or folder
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `path` - path to the item, or its identifier prefixed with "id:"
+/// * `output` - destination for the results of the command
+pub async fn stat_cmd(service: &odapi, path: &str, output: Output) -> MyResult<()> {
+    let item = find_item(service, path).await?;
+    output.print(&StatInfo::from(&item))
+}
+
+/// Entrypoint function for the 'cat' subcommand
+/// Streams the content of one or more files to stdout, one after the other
+///
+/// # Arguments
+///
+/// * `service` - client used to communicate with OneDrive
+/// * `paths` - paths to the files, or their identifiers prefixed with "id:"
+/// * `range` - portion of each file to show, or None to show the entire file
+pub async fn cat_cmd(service: &odapi, paths: &[String], range: Option<ByteRange>) -> MyResult<()> {
+    let mut writer = stdout().lock();
+    for path in paths {
+        let item = find_item(service, path).await?;
+        if !item.is_file() {
+            let msg = format!("{} is not a file", path);
+            return Err(SimpleError::new(msg).into());
+        }
+        let mut content = match range {
+            Some(r) => item.download_range(r).await?,
+            None => item.download().await?,
+        };
+        while let Some(chunk) = content.chunk().await? {
+            // Readers such as 'head' may stop reading before the end of the
+            // file, which is not considered to be an error
+            match writer.write_all(&chunk) {
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+    }
+    match writer.flush() {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
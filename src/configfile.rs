@@ -6,7 +6,7 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 use std::{error::Error, fmt::Debug};
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Configuration {
@@ -17,6 +17,16 @@ pub struct Configuration {
     /// Secondary authentication token used to renew the lifetime
     /// of the primary authentication token
     pub refresh_token: String,
+    /// Optional base URL of the Microsoft Graph REST API
+    /// Only needed when connecting to an alternative implementation
+    /// of the API, such as a mock server used for testing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// Optional base URL of the OAuth service used to authenticate
+    /// Only needed when connecting to an alternative implementation
+    /// of the service, such as a mock server used for testing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_url: Option<String>,
 }
 
 impl Configuration {
@@ -64,12 +74,15 @@ mod tests {
 
     #[test]
     fn save_config_file() {
-        let temp_file = tempdir().unwrap().into_path().join("test.yml");
+        let temp_dir = tempdir().unwrap();
+        let temp_file = temp_dir.path().join("test.yml");
         let expected_auth_token = "abcd".to_string();
         let expected_refresh_token = "1234".to_string();
         let config = Configuration {
             auth_token: expected_auth_token.clone(),
             refresh_token: expected_refresh_token.clone(),
+            api_url: None,
+            auth_url: None,
         };
         config.save(&temp_file).unwrap();
 
@@ -81,6 +94,8 @@ mod tests {
 
         assert!(actual_data.contains(&expected_auth_token));
         assert!(actual_data.contains(&expected_refresh_token));
+        assert!(!actual_data.contains("api_url"));
+        assert!(!actual_data.contains("auth_url"));
     }
 
     #[test]
@@ -97,6 +112,27 @@ mod tests {
         let config = Configuration::from_file(&test_file).unwrap();
         assert_eq!(config.auth_token, "abcdABCD");
         assert_eq!(config.refresh_token, "1234");
+        assert!(config.api_url.is_none());
+        assert!(config.auth_url.is_none());
+    }
+
+    #[test]
+    fn load_config_file_with_urls() {
+        let test_file = [
+            env!("CARGO_MANIFEST_DIR"),
+            "src",
+            "test_data",
+            "config_files",
+            "custom_urls_config_file.yml",
+        ]
+        .iter()
+        .collect();
+        let config = Configuration::from_file(&test_file).unwrap();
+        assert_eq!(
+            config.api_url.as_deref(),
+            Some("http://127.0.0.1:1234/v1.0")
+        );
+        assert_eq!(config.auth_url.as_deref(), Some("http://127.0.0.1:1234"));
     }
 
     #[test]
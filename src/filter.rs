@@ -0,0 +1,284 @@
+//! Criteria for narrowing down the files and folders found by a command,
+//! modelled on the predicates of the 'find' command
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use glob::{MatchOptions, Pattern};
+use simple_error::SimpleError;
+
+use crate::api::DriveItem;
+use crate::output::ItemType;
+
+/// Suffixes accepted by size criteria, along with the number of bytes in
+/// each unit
+const SIZE_UNITS: [(char, u64); 5] = [
+    ('c', 1),
+    ('k', 1 << 10),
+    ('M', 1 << 20),
+    ('G', 1 << 30),
+    ('T', 1 << 40),
+];
+
+/// Parses a date or timestamp provided on the command line. Dates such as
+/// "2022-06-18" refer to midnight UTC, and timestamps use the RFC 3339
+/// format, ie: "2022-06-18T14:05:14Z"
+///
+/// # Arguments
+///
+/// * `value` - Date or timestamp to parse
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>, SimpleError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| SimpleError::new(format!("Invalid date {}", value)))
+}
+
+/// Numeric criteria in the style of the 'find' command, where "+N" means
+/// more than N, "-N" means less than N and "N" means exactly N
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    /// How values must compare to the threshold to meet the criteria
+    ordering: Ordering,
+    value: u64,
+}
+
+impl Threshold {
+    /// Checks whether a value meets this criteria
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Value to check
+    fn matches(&self, value: u64) -> bool {
+        value.cmp(&self.value) == self.ordering
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = SimpleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (ordering, number) = match value.split_at(value.len().min(1)) {
+            ("+", rest) => (Ordering::Greater, rest),
+            ("-", rest) => (Ordering::Less, rest),
+            _ => (Ordering::Equal, value),
+        };
+        match number.parse() {
+            Ok(value) if !number.starts_with('+') => Ok(Threshold { ordering, value }),
+            _ => Err(SimpleError::new(format!("Invalid number {}", value))),
+        }
+    }
+}
+
+/// Size criteria in the style of the 'find' command, ie: "+10M" for files
+/// larger than 10 MiB. Sizes are rounded up to whole units before they are
+/// compared, so "-1M" only matches empty files. Sizes without a unit
+/// suffix are in bytes, rather than the 512 byte blocks used by 'find'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeThreshold {
+    threshold: Threshold,
+    /// Number of bytes in the unit the threshold is given in
+    unit: u64,
+}
+
+impl SizeThreshold {
+    /// Checks whether a size meets this criteria
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Size to check, in bytes
+    fn matches(&self, size: u64) -> bool {
+        self.threshold.matches(size.div_ceil(self.unit))
+    }
+}
+
+impl FromStr for SizeThreshold {
+    type Err = SimpleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = match SIZE_UNITS.iter().find(|(s, _)| value.ends_with(*s)) {
+            Some((suffix, unit)) => (value.trim_end_matches(*suffix), *unit),
+            None => (value, 1),
+        };
+        let threshold = number
+            .parse()
+            .map_err(|_| SimpleError::new(format!("Invalid size {}", value)))?;
+        Ok(SizeThreshold { threshold, unit })
+    }
+}
+
+/// Criteria used to narrow down the items found by a command. Items must
+/// meet all of the criteria that have been provided
+#[derive(Debug, Default, Clone)]
+pub struct ItemFilter {
+    /// Kind of item to include, or None to include items of any kind
+    pub item_type: Option<ItemType>,
+    /// Wildcard pattern the names of items must match, ie: "*.heic"
+    pub name: Option<Pattern>,
+    /// True to ignore differences in case when matching names
+    pub ignore_case: bool,
+    /// File extensions to include, without the leading ".", ie: "pdf"
+    /// If not empty, only files with one of these extensions are included
+    pub extensions: Vec<String>,
+    /// Size of the items to include
+    pub size: Option<SizeThreshold>,
+    /// Number of whole days since the items to include were last modified
+    pub modified_days: Option<Threshold>,
+    /// Only include items modified at or after this time
+    pub modified_after: Option<DateTime<Utc>>,
+    /// Only include items modified before this time
+    pub modified_before: Option<DateTime<Utc>>,
+}
+
+impl ItemFilter {
+    /// Checks whether an item meets all of the criteria of this filter
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - Item to check
+    /// * `now` - Current time, used to determine the age of the item
+    pub fn matches(&self, item: &DriveItem, now: DateTime<Utc>) -> bool {
+        if self.item_type.is_some_and(|t| t != ItemType::from(item)) {
+            return false;
+        }
+        if let Some(pattern) = &self.name {
+            let options = MatchOptions {
+                case_sensitive: !self.ignore_case,
+                ..MatchOptions::new()
+            };
+            if !pattern.matches_with(&item.name, options) {
+                return false;
+            }
+        }
+        if !self.extensions.is_empty() {
+            let extension = match item.name.rsplit_once('.') {
+                Some((_, ext)) if item.is_file() => ext,
+                _ => return false,
+            };
+            if !self
+                .extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            {
+                return false;
+            }
+        }
+        if let Some(size) = self.size {
+            if !size.matches(item.size.unwrap_or_default()) {
+                return false;
+            }
+        }
+
+        let modified = item.last_modified_date_time;
+        if let Some(days) = self.modified_days {
+            // Like the 'find' command, any fraction of a day is ignored
+            let age = modified.map(|m| (now - m).num_days().max(0) as u64);
+            if !age.is_some_and(|a| days.matches(a)) {
+                return false;
+            }
+        }
+        if let Some(after) = self.modified_after {
+            if modified.is_none_or(|m| m < after) {
+                return false;
+            }
+        }
+        if let Some(before) = self.modified_before {
+            if modified.is_none_or(|m| m >= before) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Generates a file modified at the given time
+    fn file(name: &str, size: u64, modified: &str) -> DriveItem {
+        serde_json::from_value(json!({
+            "id": "F1",
+            "name": name,
+            "size": size,
+            "file": {},
+            "lastModifiedDateTime": modified,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn dates() {
+        let expected = Utc.with_ymd_and_hms(2022, 6, 18, 0, 0, 0).unwrap();
+        assert_eq!(parse_date("2022-06-18").unwrap(), expected);
+        let expected = Utc.with_ymd_and_hms(2022, 6, 18, 12, 5, 14).unwrap();
+        assert_eq!(parse_date("2022-06-18T14:05:14+02:00").unwrap(), expected);
+        assert!(parse_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn thresholds() {
+        let threshold: Threshold = "+30".parse().unwrap();
+        assert!(threshold.matches(31));
+        assert!(!threshold.matches(30));
+        let threshold: Threshold = "-30".parse().unwrap();
+        assert!(threshold.matches(29));
+        assert!(!threshold.matches(30));
+        let threshold: Threshold = "30".parse().unwrap();
+        assert!(threshold.matches(30));
+        assert!("++30".parse::<Threshold>().is_err());
+        assert!("thirty".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn size_thresholds() {
+        let size: SizeThreshold = "+10M".parse().unwrap();
+        assert!(size.matches(10 * 1024 * 1024 + 1));
+        assert!(!size.matches(10 * 1024 * 1024));
+        let size: SizeThreshold = "-1M".parse().unwrap();
+        assert!(size.matches(0));
+        assert!(!size.matches(1));
+        let size: SizeThreshold = "2k".parse().unwrap();
+        assert!(size.matches(1025));
+        assert!(size.matches(2048));
+        let size: SizeThreshold = "-100".parse().unwrap();
+        assert!(size.matches(99));
+        assert!("10X".parse::<SizeThreshold>().is_err());
+    }
+
+    #[test]
+    fn filter_items() {
+        let now = Utc.with_ymd_and_hms(2022, 7, 18, 12, 0, 0).unwrap();
+        let photo = file("IMG_001.HEIC", 20 * 1024 * 1024, "2022-07-01T00:00:00Z");
+
+        let filter = ItemFilter {
+            name: Some(Pattern::new("*.heic").unwrap()),
+            ..ItemFilter::default()
+        };
+        assert!(!filter.matches(&photo, now));
+        let filter = ItemFilter {
+            ignore_case: true,
+            size: Some("+10M".parse().unwrap()),
+            modified_days: Some("-30".parse().unwrap()),
+            item_type: Some(ItemType::File),
+            ..filter
+        };
+        assert!(filter.matches(&photo, now));
+        let old = file("IMG_002.heic", 20 * 1024 * 1024, "2022-06-01T00:00:00Z");
+        assert!(!filter.matches(&old, now));
+
+        let filter = ItemFilter {
+            extensions: vec![".heic".to_string()],
+            modified_before: Some(parse_date("2022-07-01").unwrap()),
+            ..ItemFilter::default()
+        };
+        assert!(!filter.matches(&photo, now));
+        assert!(filter.matches(&old, now));
+    }
+}
@@ -0,0 +1,108 @@
+//! Helpers for presenting OneDrive metadata to users of the command line
+use chrono::{DateTime, Utc};
+
+/// Suffixes used when showing sizes in human readable form, in increasing
+/// powers of 1024
+const SIZE_UNITS: [&str; 6] = ["", "K", "M", "G", "T", "P"];
+
+/// Formats a size in bytes for display
+///
+/// # Arguments
+///
+/// * `bytes` - Size to format
+/// * `human` - True to scale the size to the largest sensible unit,
+///             ie: "1.5M", false to show the exact number of bytes
+pub fn format_size(bytes: u64, human: bool) -> String {
+    if !human {
+        return bytes.to_string();
+    }
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    // Like the 'ls' command, small values keep a single decimal place
+    match unit {
+        0 => bytes.to_string(),
+        _ if size < 10.0 => format!("{:.1}{}", size, SIZE_UNITS[unit]),
+        _ => format!("{:.0}{}", size, SIZE_UNITS[unit]),
+    }
+}
+
+/// Formats a timestamp for display, using "-" for unknown values
+///
+/// # Arguments
+///
+/// * `time` - Timestamp to format
+pub fn format_time(time: Option<&DateTime<Utc>>) -> String {
+    time.map_or("-".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+}
+
+/// Lays out rows of values as a table with left aligned columns
+///
+/// # Arguments
+///
+/// * `rows` - Values to show in each cell of the table. Each row is expected
+///            to contain the same number of cells
+/// * `right_aligned` - Indices of the columns to right align, such as those
+///                     containing sizes
+pub fn format_table(rows: &[Vec<String>], right_aligned: &[usize]) -> Vec<String> {
+    let mut widths = Vec::new();
+    for row in rows {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.chars().count());
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(index, cell)| match right_aligned.contains(&index) {
+                    true => format!("{:>1$}", cell, widths[index]),
+                    false => format!("{:<1$}", cell, widths[index]),
+                })
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn human_readable_sizes() {
+        assert_eq!(format_size(1536, false), "1536");
+        assert_eq!(format_size(0, true), "0");
+        assert_eq!(format_size(1023, true), "1023");
+        assert_eq!(format_size(1536, true), "1.5K");
+        assert_eq!(format_size(20 * 1024 * 1024, true), "20M");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024, true), "5.0G");
+    }
+
+    #[test]
+    fn timestamps() {
+        let time = Utc.with_ymd_and_hms(2022, 6, 18, 14, 5, 14).unwrap();
+        assert_eq!(format_time(Some(&time)), "2022-06-18 14:05");
+        assert_eq!(format_time(None), "-");
+    }
+
+    #[test]
+    fn table_layout() {
+        let rows = vec![
+            vec!["d".to_string(), "0".to_string(), "Documents".to_string()],
+            vec!["-".to_string(), "1024".to_string(), "a.txt".to_string()],
+        ];
+        let lines = format_table(&rows, &[1]);
+        assert_eq!(lines, vec!["d     0  Documents", "-  1024  a.txt"]);
+    }
+}
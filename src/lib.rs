@@ -1,50 +1,44 @@
-//! Command line tool for managing objects stored in a OneDrive service
-use clap::{Parser, Subcommand};
-use commands::{init_cmd, ls_cmd, me_cmd, upload_cmd};
-use futures::executor::block_on;
-use std::{error::Error, fmt::Debug, path::PathBuf};
-mod api;
-mod auth;
+//! Tools for managing objects stored in a OneDrive service
+//!
+//! This crate provides the `onedrive_manager` command line tool, as well as
+//! the library it is built on which may be reused by other applications:
+//!
+//! * [`api`] - asynchronous client for the OneDrive REST API, used to browse,
+//!   upload and download files and folders
+//! * [`api::blocking`] - synchronous wrapper around the asynchronous client
+//!   for applications that do not use an async runtime
+//! * [`backend`] - common interface over the storage services files can be
+//!   transferred between, such as OneDrive and the local file system, used to
+//!   copy folder hierarchies between any pair of services
+//! * [`auth`] - helpers for obtaining and renewing OneDrive authentication
+//!   tokens through the OAuth "code flow"
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::TryStreamExt;
+//! use onedrive_manager::api::{ListOptions, OneDrive};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! let service = OneDrive::builder("my-access-token").build();
+//! let folder = service.drive().await?.item_by_path("/Documents").await?;
+//!
+//! let mut children = folder.children(&ListOptions::default());
+//! while let Some(item) = children.try_next().await? {
+//!     println!("{}", item.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+pub mod api;
+pub mod auth;
+pub mod backend;
+mod cli;
 mod commands;
 mod configfile;
+mod filter;
+mod format;
+mod output;
+pub mod paths;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
-/// App for managing files on a OneDrive service
-#[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
-pub struct Args {
-    #[clap(subcommand)]
-    cmd: SubCommand,
-}
-
-#[derive(Subcommand, Debug)]
-enum SubCommand {
-    /// Initialize and authenticate the app
-    Init {
-        #[clap(short, long)]
-        /// Can we intercept authentication requests from the browser?
-        browser: bool,
-    },
-    /// List contents of root OneDrive folder
-    Ls,
-    /// Upload a new file to OneDrive root folder
-    Upload {
-        #[clap(short, long)]
-        /// Path to the file to upload
-        sourcefile: PathBuf,
-    },
-    /// Shows profile information for the currently logged in user
-    Me,
-}
-
-/// Entrypoint function for our command line interface
-pub fn run() -> MyResult<()> {
-    let args = Args::parse();
-    match args.cmd {
-        SubCommand::Init { browser } => init_cmd(browser),
-        SubCommand::Ls => block_on(ls_cmd()),
-        SubCommand::Upload { sourcefile } => block_on(upload_cmd(&sourcefile)),
-        SubCommand::Me => block_on(me_cmd()),
-    }
-}
+pub use cli::{run, Args};
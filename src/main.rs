@@ -1,12 +1,9 @@
 // Treat all enabled lints as errors
 #![deny(clippy::all)]
+use std::process::ExitCode;
 use tokio::main;
 
 #[main]
-async fn main() {
-    if let Err(e) = onedrive_manager::run() {
-        eprintln!("{}", e);
-
-        std::process::exit(1);
-    }
+async fn main() -> ExitCode {
+    onedrive_manager::run().await
 }
@@ -0,0 +1,1160 @@
+//! Presentation of command results in the output format selected by the user
+//!
+//! Every command reports its results as one or more records, which are
+//! written to stdout in one of the following formats:
+//!
+//! * `plain` - human readable text, which is the default
+//! * `table` - records laid out in aligned columns beneath a heading row
+//! * `json` - a single pretty printed JSON document. Commands that produce
+//!   several records, such as `ls`, emit an array
+//! * `ndjson` - newline delimited JSON, with one compact object per record
+//!
+//! The JSON structures are defined by the record types in this module, and
+//! only change in backwards compatible ways, such as by adding new fields.
+//! Field names use camelCase, timestamps use the RFC 3339 format and sizes
+//! are always given in bytes. Errors are written to stderr, as an object of
+//! the form `{"error": {"message": "...", "status": 404}}` when JSON output
+//! is selected. Prompts and progress messages are also written to stderr, so
+//! stdout only ever contains results
+use std::error::Error;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use simple_error::SimpleError;
+
+use crate::api::driveitem::IdentitySet;
+use crate::api::{Drive, DriveItem, Quota, User};
+use crate::format::{format_size, format_table, format_time};
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Format used to write command results to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Pretty printed JSON document
+    Json,
+    /// Newline delimited JSON, with one record per line
+    Ndjson,
+    /// Aligned columns beneath a heading row
+    Table,
+    /// Human readable text
+    Plain,
+}
+
+impl FromStr for OutputFormat {
+    type Err = SimpleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "table" => Ok(OutputFormat::Table),
+            "plain" => Ok(OutputFormat::Plain),
+            _ => Err(SimpleError::new(format!(
+                "Unsupported output format {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Command result which can be written in any of the output formats
+pub trait Record: Serialize {
+    /// Column headings used when records are shown as a table
+    const HEADINGS: &'static [&'static str];
+
+    /// Indices of the table columns to right align, such as those
+    /// containing sizes
+    const RIGHT_ALIGNED: &'static [usize] = &[];
+
+    /// Values shown in each column when the record is shown as a table
+    ///
+    /// # Arguments
+    ///
+    /// * `human` - True to show sizes in human readable form
+    fn cells(&self, human: bool) -> Vec<String>;
+
+    /// Text shown for the record in plain output
+    ///
+    /// # Arguments
+    ///
+    /// * `human` - True to show sizes in human readable form
+    fn plain(&self, human: bool) -> String;
+}
+
+/// Gets the name of the user, or failing that the application, from a set
+/// of identities associated with an action
+///
+/// # Arguments
+///
+/// * `identities` - Identities associated with the action, if known
+fn display_name(identities: Option<&IdentitySet>) -> Option<String> {
+    identities
+        .and_then(|i| i.user.as_ref().or(i.application.as_ref()))
+        .and_then(|u| u.display_name.clone())
+}
+
+/// Kind of drive item described by an [`ItemInfo`] record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemType {
+    File,
+    Folder,
+    /// Item managed by another application, such as a OneNote notebook
+    Package,
+}
+
+impl ItemType {
+    /// Single character identifying the type of item in the long listing
+    /// format, following the conventions of the 'ls' command
+    pub fn letter(&self) -> &'static str {
+        match self {
+            ItemType::File => "-",
+            ItemType::Folder => "d",
+            ItemType::Package => "p",
+        }
+    }
+}
+
+impl From<&DriveItem> for ItemType {
+    fn from(item: &DriveItem) -> Self {
+        match (item.is_folder(), item.package.is_some()) {
+            (true, _) => ItemType::Folder,
+            (false, true) => ItemType::Package,
+            (false, false) => ItemType::File,
+        }
+    }
+}
+
+impl FromStr for ItemType {
+    type Err = SimpleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            // Single letters are accepted, as used by the 'find' command
+            "file" | "f" => Ok(ItemType::File),
+            "folder" | "d" => Ok(ItemType::Folder),
+            "package" | "p" => Ok(ItemType::Package),
+            _ => Err(SimpleError::new(format!("Unsupported item type {}", value))),
+        }
+    }
+}
+
+/// Description of a single file or folder
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemInfo {
+    /// Unique identifier of the item
+    pub id: String,
+    pub name: String,
+    /// Full path to the item, ie: "/Documents/Budget.xlsx", if known
+    pub path: Option<String>,
+    #[serde(rename = "type")]
+    pub item_type: ItemType,
+    /// Size of the item in bytes. For folders, this is the total size of
+    /// all the items they contain
+    pub size: u64,
+    /// Number of items contained directly within a folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<u64>,
+    pub modified: Option<DateTime<Utc>>,
+    /// Name of the user or application that last modified the item
+    pub modified_by: Option<String>,
+    /// URL for viewing the item in a web browser
+    pub web_url: Option<String>,
+}
+
+impl From<&DriveItem> for ItemInfo {
+    fn from(item: &DriveItem) -> Self {
+        ItemInfo {
+            id: item.id.clone(),
+            name: item.name.clone(),
+            path: item.path(),
+            item_type: ItemType::from(item),
+            size: item.size.unwrap_or_default(),
+            child_count: item.folder.as_ref().and_then(|f| f.child_count),
+            modified: item.last_modified_date_time,
+            modified_by: display_name(item.last_modified_by.as_ref()),
+            web_url: item.web_url.clone(),
+        }
+    }
+}
+
+impl Record for ItemInfo {
+    const HEADINGS: &'static [&'static str] =
+        &["TYPE", "SIZE", "MODIFIED", "MODIFIED BY", "ID", "NAME"];
+    const RIGHT_ALIGNED: &'static [usize] = &[1];
+
+    fn cells(&self, human: bool) -> Vec<String> {
+        vec![
+            self.item_type.letter().to_string(),
+            format_size(self.size, human),
+            format_time(self.modified.as_ref()),
+            self.modified_by.clone().unwrap_or_else(|| "-".to_string()),
+            self.id.clone(),
+            self.name.clone(),
+        ]
+    }
+
+    fn plain(&self, _human: bool) -> String {
+        self.name.clone()
+    }
+}
+
+/// File or folder found by a search, identified by its full path
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct FoundItem(pub ItemInfo);
+
+impl FoundItem {
+    /// Full path to the item, falling back to its name if the path is unknown
+    pub fn path(&self) -> &str {
+        self.0.path.as_deref().unwrap_or(&self.0.name)
+    }
+}
+
+impl Record for FoundItem {
+    const HEADINGS: &'static [&'static str] = &["TYPE", "SIZE", "MODIFIED", "PATH"];
+    const RIGHT_ALIGNED: &'static [usize] = &[1];
+
+    fn cells(&self, human: bool) -> Vec<String> {
+        let mut retval = self.0.cells(human);
+        retval.truncate(3);
+        retval.push(self.path().to_string());
+        retval
+    }
+
+    fn plain(&self, _human: bool) -> String {
+        self.path().to_string()
+    }
+}
+
+/// Hashes of the content of a file. Which hashes are available depends on
+/// the type of drive containing the file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashInfo {
+    pub quick_xor_hash: Option<String>,
+    pub sha1_hash: Option<String>,
+    pub sha256_hash: Option<String>,
+    pub crc32_hash: Option<String>,
+}
+
+/// Details of how an item has been shared with others
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingInfo {
+    /// Who the item is shared with, being one of "anonymous", "organization"
+    /// or "users"
+    pub scope: Option<String>,
+    /// Name of the owner of the item
+    pub owner: Option<String>,
+    /// Name of the user who shared the item
+    pub shared_by: Option<String>,
+    pub shared: Option<DateTime<Utc>>,
+}
+
+/// All of the metadata describing a single file or folder
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatInfo {
+    /// Unique identifier of the item
+    pub id: String,
+    pub name: String,
+    /// Full path to the item, ie: "/Documents/Budget.xlsx", if known
+    pub path: Option<String>,
+    #[serde(rename = "type")]
+    pub item_type: ItemType,
+    /// Size of the item in bytes. For folders, this is the total size of
+    /// all the items they contain
+    pub size: u64,
+    /// Number of items contained directly within a folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<u64>,
+    /// Identifies the current version of the item, including its metadata
+    pub e_tag: Option<String>,
+    /// Identifies the current version of the content of the item
+    pub c_tag: Option<String>,
+    /// Media type of a file, ie: "text/plain"
+    pub mime_type: Option<String>,
+    /// Hashes of the content of a file, if any are available
+    pub hashes: Option<HashInfo>,
+    pub created: Option<DateTime<Utc>>,
+    /// Name of the user or application that created the item
+    pub created_by: Option<String>,
+    pub modified: Option<DateTime<Utc>>,
+    /// Name of the user or application that last modified the item
+    pub modified_by: Option<String>,
+    /// Unique identifier of the folder containing the item
+    pub parent_id: Option<String>,
+    /// Full path to the folder containing the item, if known
+    pub parent_path: Option<String>,
+    /// Details of how the item has been shared, or None if it is not shared
+    pub shared: Option<SharingInfo>,
+    /// URL for viewing the item in a web browser
+    pub web_url: Option<String>,
+}
+
+impl From<&DriveItem> for StatInfo {
+    fn from(item: &DriveItem) -> Self {
+        let file = item.file.as_ref();
+        let parent = item.parent_reference.as_ref();
+        // Parent paths take the form "/drive/root:/path/to/folder"
+        let parent_path = parent
+            .and_then(|p| p.path.as_ref())
+            .and_then(|p| p.split_once("root:"))
+            .map(|(_, p)| match p {
+                "" => "/".to_string(),
+                _ => p.to_string(),
+            });
+        StatInfo {
+            id: item.id.clone(),
+            name: item.name.clone(),
+            path: item.path(),
+            item_type: ItemType::from(item),
+            size: item.size.unwrap_or_default(),
+            child_count: item.folder.as_ref().and_then(|f| f.child_count),
+            e_tag: item.e_tag.clone(),
+            c_tag: item.c_tag.clone(),
+            mime_type: file.and_then(|f| f.mime_type.clone()),
+            hashes: file.and_then(|f| f.hashes.as_ref()).map(|h| HashInfo {
+                quick_xor_hash: h.quick_xor_hash.clone(),
+                sha1_hash: h.sha1_hash.clone(),
+                sha256_hash: h.sha256_hash.clone(),
+                crc32_hash: h.crc32_hash.clone(),
+            }),
+            created: item.created_date_time,
+            created_by: display_name(item.created_by.as_ref()),
+            modified: item.last_modified_date_time,
+            modified_by: display_name(item.last_modified_by.as_ref()),
+            parent_id: parent.and_then(|p| p.id.clone()),
+            parent_path,
+            shared: item.shared.as_ref().map(|s| SharingInfo {
+                scope: s.scope.clone(),
+                owner: display_name(s.owner.as_ref()),
+                shared_by: display_name(s.shared_by.as_ref()),
+                shared: s.shared_date_time,
+            }),
+            web_url: item.web_url.clone(),
+        }
+    }
+}
+
+impl Record for StatInfo {
+    const HEADINGS: &'static [&'static str] = &["TYPE", "SIZE", "MODIFIED", "ETAG", "ID", "PATH"];
+    const RIGHT_ALIGNED: &'static [usize] = &[1];
+
+    fn cells(&self, human: bool) -> Vec<String> {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+        vec![
+            self.item_type.letter().to_string(),
+            format_size(self.size, human),
+            format_time(self.modified.as_ref()),
+            value(&self.e_tag),
+            self.id.clone(),
+            self.path.clone().unwrap_or_else(|| self.name.clone()),
+        ]
+    }
+
+    fn plain(&self, _human: bool) -> String {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+        // Unlike other commands, the exact time of each change is shown
+        let time = |t: &Option<DateTime<Utc>>| {
+            t.map_or("-".to_string(), |t| {
+                t.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+            })
+        };
+        let by = |t: &Option<DateTime<Utc>>, who: &Option<String>| match who {
+            Some(who) => format!("{} by {}", time(t), who),
+            None => time(t),
+        };
+        let item_type = match self.item_type {
+            ItemType::File => "file",
+            ItemType::Folder => "folder",
+            ItemType::Package => "package",
+        };
+        // Exact sizes are shown, along with their human readable form
+        let size = match format_size(self.size, true) {
+            readable if readable == self.size.to_string() => readable,
+            readable => format!("{} ({})", self.size, readable),
+        };
+
+        let mut lines = vec![
+            format!("Name:          {}", self.name),
+            format!("Path:          {}", value(&self.path)),
+            format!("Type:          {}", item_type),
+            format!("ID:            {}", self.id),
+            format!("Size:          {}", size),
+        ];
+        if let Some(count) = self.child_count {
+            lines.push(format!("Items:         {}", count));
+        }
+        lines.push(format!("ETag:          {}", value(&self.e_tag)));
+        lines.push(format!("CTag:          {}", value(&self.c_tag)));
+        if self.mime_type.is_some() {
+            lines.push(format!("MIME type:     {}", value(&self.mime_type)));
+        }
+        if let Some(hashes) = &self.hashes {
+            let available = [
+                ("QuickXorHash:  ", &hashes.quick_xor_hash),
+                ("SHA1:          ", &hashes.sha1_hash),
+                ("SHA256:        ", &hashes.sha256_hash),
+                ("CRC32:         ", &hashes.crc32_hash),
+            ];
+            for (label, hash) in available {
+                if let Some(hash) = hash {
+                    lines.push(format!("{}{}", label, hash));
+                }
+            }
+        }
+        lines.push(format!(
+            "Created:       {}",
+            by(&self.created, &self.created_by)
+        ));
+        lines.push(format!(
+            "Modified:      {}",
+            by(&self.modified, &self.modified_by)
+        ));
+        if self.parent_id.is_some() {
+            lines.push(format!(
+                "Parent:        {} ({})",
+                value(&self.parent_path),
+                value(&self.parent_id)
+            ));
+        }
+        let shared = match &self.shared {
+            Some(s) => {
+                let mut text = format!("with {}", value(&s.scope));
+                if s.shared_by.is_some() {
+                    text.push_str(&format!(" by {}", value(&s.shared_by)));
+                }
+                if s.shared.is_some() {
+                    text.push_str(&format!(" on {}", time(&s.shared)));
+                }
+                text
+            }
+            None => "no".to_string(),
+        };
+        lines.push(format!("Shared:        {}", shared));
+        lines.push(format!("Web URL:       {}", value(&self.web_url)));
+        lines.join("\n")
+    }
+}
+
+/// Storage space used by and available to a drive, in bytes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaInfo {
+    pub total: Option<u64>,
+    /// Space used by all files, including those in the recycle bin
+    pub used: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Space used by files in the recycle bin
+    pub deleted: Option<u64>,
+    /// One of "normal", "nearing", "critical" or "exceeded"
+    pub state: Option<String>,
+    /// Percentage of the total space that has been used, if known
+    pub percent_used: Option<f64>,
+}
+
+impl From<&Quota> for QuotaInfo {
+    fn from(quota: &Quota) -> Self {
+        let percent_used = match (quota.used, quota.total) {
+            (Some(used), Some(total)) if total > 0 => Some(used as f64 * 100.0 / total as f64),
+            _ => None,
+        };
+        QuotaInfo {
+            total: quota.total,
+            used: quota.used,
+            remaining: quota.remaining,
+            deleted: quota.deleted,
+            state: quota.state.clone(),
+            percent_used,
+        }
+    }
+}
+
+/// Description of the drive owned by a user
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveInfo {
+    /// Unique identifier of the drive
+    pub id: String,
+    /// One of "personal", "business" or "documentLibrary"
+    pub drive_type: String,
+    /// Name of the user or group that owns the drive
+    pub owner: Option<String>,
+    pub quota: Option<QuotaInfo>,
+}
+
+impl From<&Drive> for DriveInfo {
+    fn from(drive: &Drive) -> Self {
+        DriveInfo {
+            id: drive.id.clone(),
+            drive_type: drive.drive_type.clone(),
+            owner: display_name(drive.owner.as_ref()),
+            quota: drive.quota.as_ref().map(QuotaInfo::from),
+        }
+    }
+}
+
+impl Record for DriveInfo {
+    const HEADINGS: &'static [&'static str] = &[
+        "DRIVE", "TYPE", "SIZE", "USED", "AVAIL", "DELETED", "USE%", "STATE",
+    ];
+    const RIGHT_ALIGNED: &'static [usize] = &[2, 3, 4, 5, 6];
+
+    fn cells(&self, human: bool) -> Vec<String> {
+        let size = |s: Option<u64>| s.map_or("-".to_string(), |s| format_size(s, human));
+        let quota = self.quota.as_ref();
+        vec![
+            self.id.clone(),
+            self.drive_type.clone(),
+            size(quota.and_then(|q| q.total)),
+            size(quota.and_then(|q| q.used)),
+            size(quota.and_then(|q| q.remaining)),
+            size(quota.and_then(|q| q.deleted)),
+            quota
+                .and_then(|q| q.percent_used)
+                .map_or("-".to_string(), |p| format!("{:.0}%", p)),
+            quota
+                .and_then(|q| q.state.clone())
+                .unwrap_or_else(|| "-".to_string()),
+        ]
+    }
+
+    fn plain(&self, human: bool) -> String {
+        // Like the 'df' command, usage is shown as a table even in plain output
+        let rows = vec![
+            Self::HEADINGS.iter().map(|h| h.to_string()).collect(),
+            self.cells(human),
+        ];
+        format_table(&rows, Self::RIGHT_ALIGNED).join("\n")
+    }
+}
+
+/// Storage space used by a single folder
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageInfo {
+    /// Full path to the folder
+    pub path: String,
+    /// Total size of all the files within the folder and its subfolders,
+    /// in bytes
+    pub size: u64,
+}
+
+impl Record for UsageInfo {
+    const HEADINGS: &'static [&'static str] = &["SIZE", "PATH"];
+    const RIGHT_ALIGNED: &'static [usize] = &[0];
+
+    fn cells(&self, human: bool) -> Vec<String> {
+        vec![format_size(self.size, human), self.path.clone()]
+    }
+
+    fn plain(&self, human: bool) -> String {
+        format!("{}\t{}", format_size(self.size, human), self.path)
+    }
+}
+
+/// Single item within a folder hierarchy
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeNode {
+    pub name: String,
+    /// Full path to the item, if known
+    pub path: Option<String>,
+    #[serde(rename = "type")]
+    pub item_type: ItemType,
+    /// Size of the item in bytes
+    pub size: u64,
+    /// Number of items OneDrive reports as contained directly within a
+    /// folder, including any left out of the hierarchy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<u64>,
+    /// Items contained within a folder, or None if the folder was beyond
+    /// the depth limit or the item is not a folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<TreeNode>>,
+}
+
+impl TreeNode {
+    /// Constructs a node describing a drive item, without any children
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - Item described by the node
+    pub fn new(item: &DriveItem) -> Self {
+        let info = ItemInfo::from(item);
+        TreeNode {
+            name: info.name,
+            path: info.path,
+            item_type: info.item_type,
+            size: info.size,
+            child_count: info.child_count,
+            children: None,
+        }
+    }
+
+    /// Generates the text shown for this node in the rendered tree, which
+    /// includes the number of items contained within folders
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name or path of the item to show
+    fn label(&self, name: &str) -> String {
+        match self.child_count {
+            Some(1) => format!("{} (1 item)", name),
+            Some(count) => format!("{} ({} items)", name, count),
+            None => name.to_string(),
+        }
+    }
+
+    /// Counts the folders and files below this node
+    /// Returns the number of folders, followed by the number of files
+    fn count(&self) -> (u64, u64) {
+        let mut retval = (0, 0);
+        for child in self.children.iter().flatten() {
+            let (folders, files) = child.count();
+            match child.item_type {
+                ItemType::Folder => retval.0 += folders + 1,
+                _ => retval.1 += 1,
+            }
+            retval.1 += files;
+        }
+        retval
+    }
+
+    /// Renders the children of this node with box drawing characters
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Characters drawn in front of each child, connecting
+    ///              the ancestors of this node
+    /// * `sizes` - True to show the size of each item
+    /// * `human` - True to show sizes in human readable form
+    /// * `lines` - Rendered lines of the tree
+    fn render(&self, prefix: &str, sizes: bool, human: bool, lines: &mut Vec<String>) {
+        let children = self.children.as_deref().unwrap_or_default();
+        for (index, child) in children.iter().enumerate() {
+            let last = index + 1 == children.len();
+            let size = match sizes {
+                true => format!("[{:>8}]  ", format_size(child.size, human)),
+                false => String::new(),
+            };
+            let branch = if last { "└── " } else { "├── " };
+            let label = child.label(&child.name);
+            lines.push(format!("{}{}{}{}", prefix, branch, size, label));
+            let indent = if last { "    " } else { "│   " };
+            child.render(&format!("{}{}", prefix, indent), sizes, human, lines);
+        }
+    }
+}
+
+/// Folder hierarchy below a folder, along with summary counts
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeInfo {
+    /// Folder at the top of the hierarchy, along with all of its descendants
+    pub tree: TreeNode,
+    /// Number of folders below the top folder
+    pub directories: u64,
+    /// Number of files below the top folder
+    pub files: u64,
+    /// True to show the size of each item in text based formats
+    #[serde(skip)]
+    pub sizes: bool,
+}
+
+impl TreeInfo {
+    /// Constructs a new instance of the TreeInfo struct
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - Folder at the top of the hierarchy
+    /// * `sizes` - True to show the size of each item in text based formats
+    pub fn new(tree: TreeNode, sizes: bool) -> Self {
+        let (directories, files) = tree.count();
+        TreeInfo {
+            tree,
+            directories,
+            files,
+            sizes,
+        }
+    }
+}
+
+impl Record for TreeInfo {
+    const HEADINGS: &'static [&'static str] = &["PATH", "DIRECTORIES", "FILES"];
+    const RIGHT_ALIGNED: &'static [usize] = &[1, 2];
+
+    fn cells(&self, _human: bool) -> Vec<String> {
+        vec![
+            self.tree
+                .path
+                .clone()
+                .unwrap_or_else(|| self.tree.name.clone()),
+            self.directories.to_string(),
+            self.files.to_string(),
+        ]
+    }
+
+    fn plain(&self, human: bool) -> String {
+        let path = self.tree.path.as_ref().unwrap_or(&self.tree.name);
+        let mut lines = vec![self.tree.label(path)];
+        self.tree.render("", self.sizes, human, &mut lines);
+        let plural = |count: u64, one: &str, many: &str| match count {
+            1 => format!("1 {}", one),
+            _ => format!("{} {}", count, many),
+        };
+        lines.push(String::new());
+        lines.push(format!(
+            "{}, {}",
+            plural(self.directories, "directory", "directories"),
+            plural(self.files, "file", "files")
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Profile of the currently logged in user
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    /// Unique identifier of the user
+    pub id: String,
+    pub display_name: String,
+    /// Primary email address of the user, if they have one
+    pub email: Option<String>,
+    /// Name the user logs in with, typically also an email address
+    pub user_principal_name: String,
+    pub job_title: Option<String>,
+    /// Language preferred by the user, ie: "en-US"
+    pub preferred_language: Option<String>,
+    /// Default drive of the user, if they have one
+    pub drive: Option<DriveInfo>,
+}
+
+impl UserInfo {
+    /// Constructs a new instance of the UserInfo struct
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - Profile data for the user
+    /// * `drive` - Default drive of the user, if they have one
+    pub fn new(user: &User, drive: Option<&Drive>) -> Self {
+        UserInfo {
+            id: user.id.clone(),
+            display_name: user.display_name.clone(),
+            email: user.mail.clone(),
+            user_principal_name: user.user_principal_name.clone(),
+            job_title: user.job_title.clone(),
+            preferred_language: user.preferred_language.clone(),
+            drive: drive.map(DriveInfo::from),
+        }
+    }
+}
+
+impl Record for UserInfo {
+    const HEADINGS: &'static [&'static str] = &["ID", "NAME", "EMAIL", "DRIVE", "USED", "TOTAL"];
+    const RIGHT_ALIGNED: &'static [usize] = &[4, 5];
+
+    fn cells(&self, human: bool) -> Vec<String> {
+        let quota = self.drive.as_ref().and_then(|d| d.quota.as_ref());
+        let size = |s: Option<u64>| s.map_or("-".to_string(), |s| format_size(s, human));
+        vec![
+            self.id.clone(),
+            self.display_name.clone(),
+            self.email.clone().unwrap_or_else(|| "-".to_string()),
+            self.drive
+                .as_ref()
+                .map_or("-".to_string(), |d| d.drive_type.clone()),
+            size(quota.and_then(|q| q.used)),
+            size(quota.and_then(|q| q.total)),
+        ]
+    }
+
+    fn plain(&self, _human: bool) -> String {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+        let mut lines = vec![
+            format!("Name:       {}", self.display_name),
+            format!("Login:      {}", self.user_principal_name),
+            format!("Email:      {}", value(&self.email)),
+            format!("Job title:  {}", value(&self.job_title)),
+            format!("Language:   {}", value(&self.preferred_language)),
+            format!("ID:         {}", self.id),
+        ];
+        if let Some(drive) = &self.drive {
+            lines.push(format!("Drive:      {} ({})", drive.drive_type, drive.id));
+            lines.push(format!("Owner:      {}", value(&drive.owner)));
+        }
+        // Storage sizes are always easier to read in human readable form
+        if let Some(quota) = self.drive.as_ref().and_then(|d| d.quota.as_ref()) {
+            let size = |s: Option<u64>| s.map_or("-".to_string(), |s| format_size(s, true));
+            lines.push(format!(
+                "Storage:    {} used of {}, {} remaining, {} deleted",
+                size(quota.used),
+                size(quota.total),
+                size(quota.remaining),
+                size(quota.deleted)
+            ));
+            lines.push(format!("State:      {}", value(&quota.state)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Operations reported by an [`ActionResult`] record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    /// Authentication tokens were saved to the app configuration file
+    Initialized,
+    Created,
+    Uploaded,
+    Deleted,
+    /// The user declined to perform the operation
+    Cancelled,
+    Moved,
+    Copied,
+    /// A copy was started and continues to run in the background
+    CopyStarted,
+}
+
+/// Outcome of a command that modifies items stored in OneDrive
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionResult {
+    pub action: Action,
+    /// Path of the item the operation was performed on
+    pub path: String,
+    /// New path of the item, for operations that move or copy it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// Item produced by the operation, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<ItemInfo>,
+    /// URL reporting the progress of an operation running in the background
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_url: Option<String>,
+    /// True if the item was deleted without using the recycle bin
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub permanent: bool,
+    /// True if the operation was only simulated, and nothing was changed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+impl ActionResult {
+    /// Constructs a record for an operation with no additional details
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - Operation that was performed
+    /// * `path` - Path of the item the operation was performed on
+    pub fn new(action: Action, path: &str) -> Self {
+        ActionResult {
+            action,
+            path: path.to_string(),
+            destination: None,
+            item: None,
+            monitor_url: None,
+            permanent: false,
+            dry_run: false,
+        }
+    }
+
+    /// Constructs a record for an operation that produced a drive item
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - Operation that was performed
+    /// * `path` - Path of the item the operation was performed on
+    /// * `item` - Item produced by the operation
+    pub fn with_item(action: Action, path: &str, item: &DriveItem) -> Self {
+        ActionResult {
+            item: Some(ItemInfo::from(item)),
+            ..ActionResult::new(action, path)
+        }
+    }
+}
+
+impl Record for ActionResult {
+    const HEADINGS: &'static [&'static str] = &["ACTION", "PATH", "DESTINATION"];
+
+    fn cells(&self, _human: bool) -> Vec<String> {
+        // Reuse the names from the JSON output, ie: "copyStarted"
+        let action = serde_json::to_value(self.action).unwrap_or_default();
+        vec![
+            action.as_str().unwrap_or_default().to_string(),
+            self.path.clone(),
+            self.destination.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn plain(&self, _human: bool) -> String {
+        let destination = self.destination.as_deref().unwrap_or_default();
+        match self.action {
+            Action::Initialized => format!("Configuration saved to {}", self.path),
+            Action::Created => format!("Created {}", self.path),
+            Action::Uploaded => format!(
+                "Successfully uploaded {}",
+                self.item.as_ref().map_or(self.path.as_str(), |i| &i.name)
+            ),
+            Action::Deleted if self.dry_run => {
+                match self.item.as_ref().and_then(|i| i.child_count) {
+                    Some(count) => format!(
+                        "Would delete {} and the {} item(s) it contains",
+                        self.path, count
+                    ),
+                    None => format!("Would delete {}", self.path),
+                }
+            }
+            Action::Deleted if self.permanent => format!("Permanently deleted {}", self.path),
+            Action::Deleted => format!("Deleted {}", self.path),
+            Action::Cancelled => "Nothing deleted".to_string(),
+            Action::Moved => format!("Moved {} to {}", self.path, destination),
+            Action::Copied => format!("Copied {} to {}", self.path, destination),
+            Action::CopyStarted => format!(
+                "Copy started, progress is available from {}",
+                self.monitor_url.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Description of an error reported to the user
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorInfo {
+    pub message: String,
+    /// HTTP status code returned by OneDrive, if the error was caused by
+    /// a failed request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl From<&(dyn Error + Send + Sync + 'static)> for ErrorInfo {
+    fn from(error: &(dyn Error + Send + Sync + 'static)) -> Self {
+        let status = error
+            .downcast_ref::<reqwest::Error>()
+            .and_then(|e| e.status())
+            .map(|s| s.as_u16());
+        ErrorInfo {
+            message: error.to_string(),
+            status,
+        }
+    }
+}
+
+/// Writes command results to stdout in the format selected by the user
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    pub format: OutputFormat,
+    /// True to show sizes in human readable form in text based formats
+    pub human: bool,
+}
+
+impl Output {
+    /// Constructs a new instance of the Output struct
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Format used to write all results
+    pub fn new(format: OutputFormat) -> Self {
+        Output {
+            format,
+            human: false,
+        }
+    }
+
+    /// Creates a copy of this output that shows sizes in human readable form
+    ///
+    /// # Arguments
+    ///
+    /// * `human` - True to show sizes in human readable form
+    pub fn human(self, human: bool) -> Self {
+        Output { human, ..self }
+    }
+
+    /// Writes a single record
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - Result to write
+    pub fn print<T: Record>(&self, record: &T) -> MyResult<()> {
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(record)?),
+            OutputFormat::Ndjson => println!("{}", serde_json::to_string(record)?),
+            OutputFormat::Table => self.print_table(std::slice::from_ref(record), true),
+            OutputFormat::Plain => println!("{}", record.plain(self.human)),
+        }
+        Ok(())
+    }
+
+    /// Writes a collection of records
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - Results to write
+    pub fn print_all<T: Record>(&self, records: &[T]) -> MyResult<()> {
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(records)?),
+            OutputFormat::Ndjson => {
+                for r in records {
+                    println!("{}", serde_json::to_string(r)?);
+                }
+            }
+            OutputFormat::Table => self.print_table(records, true),
+            OutputFormat::Plain => {
+                for r in records {
+                    println!("{}", r.plain(self.human));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a collection of records as a table, regardless of the
+    /// selected output format
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - Results to write
+    /// * `headings` - True to show the column headings above the records
+    pub fn print_table<T: Record>(&self, records: &[T], headings: bool) {
+        if records.is_empty() {
+            return;
+        }
+        let mut rows = Vec::new();
+        if headings {
+            rows.push(T::HEADINGS.iter().map(|h| h.to_string()).collect());
+        }
+        rows.extend(records.iter().map(|r| r.cells(self.human)));
+        for line in format_table(&rows, T::RIGHT_ALIGNED) {
+            println!("{}", line);
+        }
+    }
+
+    /// Reports an error to the user on stderr
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - Error that caused a command to fail
+    pub fn print_error(&self, error: &(dyn Error + Send + Sync + 'static)) {
+        let info = serde_json::json!({ "error": ErrorInfo::from(error) });
+        match self.format {
+            OutputFormat::Json => eprintln!("{:#}", info),
+            OutputFormat::Ndjson => eprintln!("{}", info),
+            OutputFormat::Table | OutputFormat::Plain => eprintln!("{}", error),
+        }
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_item() -> ItemInfo {
+        ItemInfo {
+            id: "ABC123!102".to_string(),
+            name: "Budget.xlsx".to_string(),
+            path: Some("/Documents/Budget.xlsx".to_string()),
+            item_type: ItemType::File,
+            size: 1536,
+            child_count: None,
+            modified: "2022-06-18T14:05:14Z".parse().ok(),
+            modified_by: None,
+            web_url: None,
+        }
+    }
+
+    #[test]
+    fn parse_output_format() {
+        assert_eq!(
+            "ndjson".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Ndjson
+        );
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn item_json_structure() {
+        let actual = serde_json::to_value(sample_item()).unwrap();
+        let expected = json!({
+            "id": "ABC123!102",
+            "name": "Budget.xlsx",
+            "path": "/Documents/Budget.xlsx",
+            "type": "file",
+            "size": 1536,
+            "modified": "2022-06-18T14:05:14Z",
+            "modifiedBy": null,
+            "webUrl": null
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn action_json_structure() {
+        let mut result = ActionResult::new(Action::CopyStarted, "/a.txt");
+        result.destination = Some("/b.txt".to_string());
+        result.monitor_url = Some("https://example.com/monitor".to_string());
+        let actual = serde_json::to_value(&result).unwrap();
+        let expected = json!({
+            "action": "copyStarted",
+            "path": "/a.txt",
+            "destination": "/b.txt",
+            "monitorUrl": "https://example.com/monitor"
+        });
+        assert_eq!(actual, expected);
+        assert_eq!(result.cells(false), vec!["copyStarted", "/a.txt", "/b.txt"]);
+    }
+
+    #[test]
+    fn render_tree() {
+        let node = |name: &str, item_type, children: Option<Vec<TreeNode>>| TreeNode {
+            name: name.to_string(),
+            path: Some(format!("/{}", name)),
+            item_type,
+            size: 1536,
+            child_count: children.as_ref().map(|c| c.len() as u64),
+            children,
+        };
+        let tree = node(
+            "Documents",
+            ItemType::Folder,
+            Some(vec![
+                node(
+                    "Reports",
+                    ItemType::Folder,
+                    Some(vec![node("b.txt", ItemType::File, None)]),
+                ),
+                node("a.txt", ItemType::File, None),
+            ]),
+        );
+
+        let info = TreeInfo::new(tree, true);
+        assert_eq!((info.directories, info.files), (1, 2));
+        let expected = "/Documents (2 items)\n\
+                        ├── [    1.5K]  Reports (1 item)\n\
+                        │   └── [    1.5K]  b.txt\n\
+                        └── [    1.5K]  a.txt\n\
+                        \n\
+                        1 directory, 2 files";
+        assert_eq!(info.plain(true), expected);
+    }
+
+    #[test]
+    fn plain_action_messages() {
+        let mut result = ActionResult::new(Action::Deleted, "/Documents");
+        assert_eq!(result.plain(false), "Deleted /Documents");
+        result.permanent = true;
+        assert_eq!(result.plain(false), "Permanently deleted /Documents");
+        result.dry_run = true;
+        assert_eq!(result.plain(false), "Would delete /Documents");
+    }
+}
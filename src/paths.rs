@@ -0,0 +1,54 @@
+//! Helpers for manipulating the "/" separated paths used to identify items
+//! in OneDrive and in every storage backend
+use std::error::Error;
+
+use simple_error::SimpleError;
+type MyResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Joins the path of a folder with the name of an item contained within it
+///
+/// # Arguments
+///
+/// * `folder` - Path to the parent folder
+/// * `name` - Name of the item within the folder
+pub fn join_path(folder: &str, name: &str) -> String {
+    format!("{}/{}", folder.trim_end_matches('/'), name)
+}
+
+/// Splits a path into the path of its parent folder and the name of the item
+///
+/// # Arguments
+///
+/// * `path` - Path to split, ie: "/Documents/Reports"
+pub fn split_path(path: &str) -> MyResult<(&str, &str)> {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some((parent, name)) if !name.is_empty() => Ok((parent, name)),
+        None if !trimmed.is_empty() => Ok(("", trimmed)),
+        _ => Err(SimpleError::new(format!("Invalid path {}", path)).into()),
+    }
+}
+
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+//                              UNIT TESTS
+//-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_paths() {
+        assert_eq!(join_path("/", "a.txt"), "/a.txt");
+        assert_eq!(join_path("", "a.txt"), "/a.txt");
+        assert_eq!(join_path("/docs/", "a.txt"), "/docs/a.txt");
+    }
+
+    #[test]
+    fn split_paths() {
+        assert_eq!(split_path("/Documents").unwrap(), ("", "Documents"));
+        assert_eq!(split_path("/a/b/c/").unwrap(), ("/a/b", "c"));
+        assert_eq!(split_path("Documents").unwrap(), ("", "Documents"));
+        assert!(split_path("/").is_err());
+        assert!(split_path("").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use common::*;
+use futures::{StreamExt, TryStreamExt};
+use mockito::Matcher;
+use onedrive_manager::api::{ByteRange, DriveItem, ListOptions, OneDrive};
+use onedrive_manager::backend::{copy, Backend, LocalBackend, OneDriveBackend};
+use serde_json::json;
+use std::error::Error;
+use std::fs::{read_to_string, write};
+use tempfile::tempdir;
+use tokio::runtime::Runtime;
+
+type TestResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+/// Constructs a client for the library API connected to a mock service
+fn client(service: &MockOneDrive) -> OneDrive {
+    OneDrive::builder(ACCESS_TOKEN)
+        .base_url(&service.url())
+        .build()
+}
+
+#[test]
+fn item_by_path() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_drive();
+    let lookup = service.mock_get(
+        "/me/drive/root:/My%20Documents/Budget.xlsx:",
+        &file_json("F1", "Budget.xlsx", "/drive/root:/My Documents", 100),
+    );
+
+    let item = Runtime::new()?.block_on(async {
+        let drive = client(&service).drive().await?;
+        drive.item_by_path("/My Documents/Budget.xlsx").await
+    })?;
+    lookup.assert();
+    assert_eq!(item.id, "F1");
+    assert_eq!(item.path().as_deref(), Some("/My Documents/Budget.xlsx"));
+    Ok(())
+}
+
+#[test]
+fn malformed_item() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_drive();
+    service.mock_get("/me/drive/root:/a.txt:", &json!({"name": "a.txt"}));
+
+    // Responses which do not describe a drive item must be reported as
+    // errors rather than crashing the caller
+    let result = Runtime::new()?.block_on(async {
+        let drive = client(&service).drive().await?;
+        drive.item_by_path("/a.txt").await
+    });
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn detached_item() -> TestResult {
+    // Items deserialized by callers have no client to send requests with
+    let item: DriveItem = serde_json::from_value(folder_json("F1", "a", "/drive/root:"))?;
+    let rt = Runtime::new()?;
+    let error = rt.block_on(item.reload()).unwrap_err();
+    assert_eq!(error.to_string(), "Item is not bound to a client");
+    let children: Vec<_> = rt.block_on(item.children(&ListOptions::default()).collect());
+    assert_eq!(children.len(), 1);
+    assert!(children[0].is_err());
+    Ok(())
+}
+
+#[test]
+fn list_with_limit() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    let pages = service.mock_children(
+        ROOT_ID,
+        &[
+            vec![
+                file_json("F1", "a.txt", "/drive/root:", 1),
+                file_json("F2", "b.txt", "/drive/root:", 1),
+            ],
+            vec![file_json("F3", "c.txt", "/drive/root:", 1)],
+        ],
+    );
+
+    let names: Vec<String> = Runtime::new()?.block_on(async {
+        let root = client(&service).me().await?.root().await?;
+        let options = ListOptions {
+            page_size: None,
+            limit: Some(2),
+        };
+        root.children(&options)
+            .map_ok(|i| i.name)
+            .try_collect()
+            .await
+    })?;
+    assert_eq!(names, vec!["a.txt", "b.txt"]);
+    // Subsequent pages must not be loaded once the limit has been reached
+    pages[0].assert();
+    assert!(!pages[1].matched());
+    Ok(())
+}
+
+#[test]
+fn download_follows_redirect() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_drive();
+    let item = service.mock_get(
+        "/me/drive/items/F1",
+        &file_json("F1", "hello.txt", "/drive/root:", 11),
+    );
+    let content = service
+        .server
+        .mock(
+            "GET",
+            format!("/drives/{}/items/F1/content", DRIVE_ID).as_str(),
+        )
+        .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+        .with_status(302)
+        .with_header("location", &format!("{}/download/F1", service.url()))
+        .create();
+    // Our access token must never be shared with the download service
+    let download = service
+        .server
+        .mock("GET", "/download/F1")
+        .match_header("authorization", Matcher::Missing)
+        .with_body("hello world")
+        .create();
+
+    let mut output = Vec::new();
+    let size = Runtime::new()?.block_on(async {
+        let drive = client(&service).drive().await?;
+        let file = drive.item_by_id("F1").await?;
+        file.download_to(&mut output).await
+    });
+    item.assert();
+    content.assert();
+    download.assert();
+    assert_eq!(size?, 11);
+    assert_eq!(output, b"hello world");
+    Ok(())
+}
+
+#[test]
+fn download_range() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_drive();
+    service.mock_get(
+        "/me/drive/items/F1",
+        &file_json("F1", "hello.txt", "/drive/root:", 11),
+    );
+    let (content, download) = service.mock_download("F1", Some("bytes=6-"), "world");
+
+    let text = Runtime::new()?.block_on(async {
+        let drive = client(&service).drive().await?;
+        let file = drive.item_by_id("F1").await?;
+        let response = file.download_range(ByteRange::From(6)).await?;
+        Ok::<_, Box<dyn Error + Send + Sync>>(response.text().await?)
+    })?;
+    content.assert();
+    download.assert();
+    assert_eq!(text, "world");
+    Ok(())
+}
+
+#[test]
+fn copy_local_file_to_onedrive() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let temp_dir = tempdir()?;
+    write(temp_dir.path().join("hello.txt"), "hello world")?;
+
+    service.mock_root();
+    let (session, upload) = service.mock_upload_session(ROOT_ID, "hello.txt", "hello world");
+    service.mock_children(
+        ROOT_ID,
+        &[vec![file_json("NEW1", "hello.txt", "/drive/root:", 11)]],
+    );
+
+    let source = LocalBackend::new(temp_dir.path());
+    let dest = OneDriveBackend::new(client(&service));
+    let entries = Runtime::new()?.block_on(async {
+        let count = copy(&source, "/hello.txt", &dest, "/hello.txt").await?;
+        assert_eq!(count, 1);
+        dest.list("/").await
+    })?;
+    session.assert();
+    upload.assert();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].path, "/hello.txt");
+    assert_eq!(entries[0].size, 11);
+    assert!(!entries[0].is_folder);
+    Ok(())
+}
+
+#[test]
+fn copy_onedrive_file_to_local() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let temp_dir = tempdir()?;
+    let content = "hello world";
+    service.mock_get(
+        "/me/drive/root:/hello.txt:",
+        &file_json("F1", "hello.txt", "/drive/root:", content.len() as u64),
+    );
+    let (request, download) = service.mock_download("F1", None, content);
+
+    let source = OneDriveBackend::new(client(&service));
+    let dest = LocalBackend::new(temp_dir.path());
+    let count = Runtime::new()?.block_on(copy(&source, "/hello.txt", &dest, "/copy.txt"))?;
+    request.assert();
+    download.assert();
+    assert_eq!(count, 1);
+    assert_eq!(read_to_string(temp_dir.path().join("copy.txt"))?, content);
+    Ok(())
+}
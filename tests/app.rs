@@ -1,9 +1,15 @@
+mod common;
+
 use assert_cmd::Command;
+use chrono::{Duration, Utc};
+use common::*;
 use predicates::prelude::*;
+use serde_json::{json, Value};
 use std::error::Error;
+use std::fs::write;
+use tempfile::tempdir;
 
 type TestResult = Result<(), Box<dyn Error>>;
-const APP_NAME: &str = "onedrive_manager";
 
 #[test]
 #[should_panic]
@@ -40,3 +46,1490 @@ fn cli_failure() -> TestResult {
     Command::cargo_bin(APP_NAME)?.assert().failure();
     Ok(())
 }
+
+#[test]
+fn me_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let me = service.mock_me();
+    let drive = service.mock_drive();
+
+    service
+        .command()
+        .arg("me")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "Name:       Kevin Phillips\n\
+             Login:      kevin@example.com\n\
+             Email:      kevin@example.com\n\
+             Job title:  -\n\
+             Language:   en-US\n\
+             ID:         741a798783ae5a91\n\
+             Drive:      personal (abc123)\n\
+             Owner:      Kevin Phillips\n\
+             Storage:    1.0G used of 5.0G, 4.0G remaining, 10M deleted\n\
+             State:      normal\n",
+        ));
+    me.assert();
+    drive.assert();
+    Ok(())
+}
+
+#[test]
+fn me_without_optional_fields() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_get(
+        "/me",
+        &json!({
+            "displayName": "Service Account",
+            "id": "1234",
+            "userPrincipalName": "service@example.com"
+        }),
+    );
+    service.mock_missing("/me/drive");
+
+    let result = service
+        .command()
+        .args(["me", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    let expected = json!({
+        "id": "1234",
+        "displayName": "Service Account",
+        "email": null,
+        "userPrincipalName": "service@example.com",
+        "jobTitle": null,
+        "preferredLanguage": null,
+        "drive": null
+    });
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn ls_follows_pagination() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    let pages = service.mock_children(
+        ROOT_ID,
+        &[
+            vec![
+                folder_json("F1", "Documents", "/drive/root:"),
+                file_json("F2", "first.txt", "/drive/root:", 10),
+            ],
+            vec![file_json("F3", "second.txt", "/drive/root:", 20)],
+        ],
+    );
+
+    service
+        .command()
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Documents"))
+        .stdout(predicate::str::contains("first.txt"))
+        .stdout(predicate::str::contains("second.txt"));
+    for page in pages {
+        page.assert();
+    }
+    Ok(())
+}
+
+#[test]
+fn ls_long_format() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let mut large = file_json("F2", "large.txt", "/drive/root:/Documents", 1536);
+    large["lastModifiedBy"] = json!({ "user": { "displayName": "Kevin Phillips" } });
+    service.mock_children(
+        "F1",
+        &[vec![
+            file_json("F3", "small.txt", "/drive/root:/Documents", 10),
+            large,
+        ]],
+    );
+
+    service
+        .command()
+        .args(["ls", "/Documents", "-l", "-h", "--sort", "size"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "-  1.5K  2022-06-18 14:05  Kevin Phillips  F2  large.txt\n\
+             -    10  2022-06-18 14:05  -               F3  small.txt\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn ls_hidden_items() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    let mut notebook = folder_json("F2", "Notebook", "/drive/root:");
+    notebook["package"] = json!({ "type": "oneNote" });
+    notebook.as_object_mut().unwrap().remove("folder");
+    service.mock_children(
+        ROOT_ID,
+        &[vec![
+            file_json("F1", ".hidden", "/drive/root:", 10),
+            notebook,
+            file_json("F3", "visible.txt", "/drive/root:", 10),
+        ]],
+    );
+
+    service
+        .command()
+        .arg("ls")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("visible.txt\n"));
+    service
+        .command()
+        .args(["ls", "-a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(".hidden\nNotebook\nvisible.txt\n"));
+    Ok(())
+}
+
+#[test]
+fn ls_recursive() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_children(
+        ROOT_ID,
+        &[vec![
+            folder_json("F1", "Documents", "/drive/root:"),
+            file_json("F2", "a.txt", "/drive/root:", 10),
+        ]],
+    );
+    service.mock_children(
+        "F1",
+        &[vec![file_json("F3", "b.txt", "/drive/root:/Documents", 10)]],
+    );
+
+    service
+        .command()
+        .args(["ls", "-R"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/:\na.txt\nDocuments\n\n/Documents:\nb.txt\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn ls_recursive_without_parent_paths() -> TestResult {
+    // Items in some listings have no path for their parent folder, so the
+    // headers must be built from the paths walked so far
+    let without_path = |mut item: Value| {
+        item["parentReference"]
+            .as_object_mut()
+            .unwrap()
+            .remove("path");
+        item
+    };
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_children(
+        ROOT_ID,
+        &[vec![without_path(folder_json("F1", "Documents", ""))]],
+    );
+    service.mock_children(
+        "F1",
+        &[vec![without_path(folder_json("F2", "Reports", ""))]],
+    );
+    service.mock_children("F2", &[vec![without_path(file_json("F3", "c.txt", "", 1))]]);
+
+    service
+        .command()
+        .args(["ls", "-R"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/:\nDocuments\n\n/Documents:\nReports\n\n/Documents/Reports:\nc.txt\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn upload_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let content = "hello world";
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("hello.txt");
+    write(&source, content)?;
+
+    service.mock_me();
+    service.mock_root();
+    let (session, upload) = service.mock_upload_session(ROOT_ID, "hello.txt", content);
+
+    service
+        .command()
+        .arg("upload")
+        .arg("--sourcefile")
+        .arg(&source)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully uploaded hello.txt"));
+    session.assert();
+    upload.assert();
+    Ok(())
+}
+
+#[test]
+fn refresh_expired_token() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.write_config("expired-token", true);
+    let expired = service.mock_expired_token("/me", "expired-token");
+    let refresh = service.mock_token_refresh(ACCESS_TOKEN, "new-refresh-token");
+    service.mock_me();
+    service.mock_drive();
+
+    service
+        .command()
+        .arg("me")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Kevin Phillips"));
+    expired.assert();
+    refresh.assert();
+
+    // Renewed tokens must be saved for use by subsequent commands
+    let config = service.config();
+    assert!(config.contains(ACCESS_TOKEN));
+    assert!(config.contains("new-refresh-token"));
+    assert!(config.contains(&service.url()));
+    Ok(())
+}
+
+#[test]
+fn api_url_from_environment() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.write_config(ACCESS_TOKEN, false);
+    let me = service.mock_me();
+    service.mock_drive();
+
+    service
+        .command()
+        .env("ONEDRIVE_API_URL", service.url())
+        .arg("me")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Kevin Phillips"));
+    me.assert();
+    Ok(())
+}
+
+#[test]
+fn mkdir_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let create =
+        service.mock_create_folder("F1", "/drive/root:/Documents", "F2", "Reports", "rename");
+
+    service
+        .command()
+        .args(["mkdir", "/Documents/Reports", "--conflict", "rename"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created /Documents/Reports"));
+    create.assert();
+    Ok(())
+}
+
+#[test]
+fn mkdir_missing_parent() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_missing("/me/drive/root:/Documents:");
+
+    service
+        .command()
+        .args(["mkdir", "/Documents/Reports"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("404"));
+    Ok(())
+}
+
+#[test]
+fn mkdir_with_parents() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_get(
+        &format!("/drives/{}/items/{}:/Documents:", DRIVE_ID, ROOT_ID),
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    service.mock_missing(&format!("/drives/{}/items/F1:/Reports:", DRIVE_ID));
+    let reports =
+        service.mock_create_folder("F1", "/drive/root:/Documents", "F2", "Reports", "fail");
+    service.mock_missing(&format!("/drives/{}/items/F2:/2022:", DRIVE_ID));
+    let year =
+        service.mock_create_folder("F2", "/drive/root:/Documents/Reports", "F3", "2022", "fail");
+
+    service
+        .command()
+        .args(["mkdir", "-p", "/Documents/Reports/2022"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created /Documents\n").not())
+        .stdout(predicate::str::contains(
+            "Created /Documents/Reports\nCreated /Documents/Reports/2022",
+        ));
+    reports.assert();
+    year.assert();
+    Ok(())
+}
+
+#[test]
+fn mkdir_with_parents_existing_folder() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_get(
+        &format!("/drives/{}/items/{}:/Documents:", DRIVE_ID, ROOT_ID),
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let create = service
+        .mock_create_folder("F1", "/drive/root:", "F1", "Documents", "fail")
+        .expect(0);
+
+    service
+        .command()
+        .args(["mkdir", "-p", "/Documents"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    create.assert();
+    Ok(())
+}
+
+#[test]
+fn rm_file() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/notes.txt:",
+        &file_json("F1", "notes.txt", "/drive/root:", 10),
+    );
+    let delete = service.mock_delete("F1");
+
+    service
+        .command()
+        .args(["rm", "/notes.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted /notes.txt"));
+    delete.assert();
+    Ok(())
+}
+
+#[test]
+fn rm_folder_requires_recursive() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let delete = service.mock_delete("F1").expect(0);
+
+    service
+        .command()
+        .args(["rm", "/Documents"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use -r"));
+    delete.assert();
+    Ok(())
+}
+
+#[test]
+fn rm_folder_confirmation() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let delete = service.mock_delete("F1").expect(1);
+
+    service
+        .command()
+        .args(["rm", "-r", "/Documents"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing deleted"));
+    service
+        .command()
+        .args(["rm", "-r", "/Documents"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted /Documents"));
+    delete.assert();
+    Ok(())
+}
+
+#[test]
+fn rm_dry_run() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let delete = service.mock_delete("F1").expect(0);
+
+    service
+        .command()
+        .args(["rm", "-r", "--yes", "--dry-run", "/Documents"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would delete /Documents"));
+    delete.assert();
+    Ok(())
+}
+
+#[test]
+fn rm_permanent() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    let mut business_file = file_json("F1", "notes.txt", "/drive/root:", 10);
+    business_file["parentReference"]["driveType"] = "business".into();
+    service.mock_get("/me/drive/root:/notes.txt:", &business_file);
+    service.mock_get(
+        "/me/drive/root:/personal.txt:",
+        &file_json("F2", "personal.txt", "/drive/root:", 10),
+    );
+    let delete = service.mock_permanent_delete("F1");
+
+    service
+        .command()
+        .args(["rm", "--permanent", "/notes.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permanently deleted /notes.txt"));
+    service
+        .command()
+        .args(["rm", "--permanent", "/personal.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not supported"));
+    delete.assert();
+    Ok(())
+}
+
+#[test]
+fn mv_rename_in_place() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_get(
+        "/me/drive/root:/a.txt:",
+        &file_json("F1", "a.txt", "/drive/root:", 10),
+    );
+    service.mock_missing("/me/drive/root:/b.txt:");
+    let moved = file_json("F1", "b.txt", "/drive/root:", 10);
+    let patch = service.mock_move("F1", ROOT_ID, "fail", &moved);
+
+    service
+        .command()
+        .args(["mv", "/a.txt", "/b.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved /a.txt to /b.txt"));
+    patch.assert();
+    Ok(())
+}
+
+#[test]
+fn mv_into_folder() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/a.txt:",
+        &file_json("F1", "a.txt", "/drive/root:", 10),
+    );
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F2", "Documents", "/drive/root:"),
+    );
+    let moved = file_json("F1", "a.txt", "/drive/root:/Documents", 10);
+    let patch = service.mock_move("F1", "F2", "replace", &moved);
+
+    service
+        .command()
+        .args(["mv", "/a.txt", "/Documents", "--conflict", "replace"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved /a.txt to /Documents/a.txt"));
+    patch.assert();
+    Ok(())
+}
+
+#[test]
+fn mv_by_id() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/items/F1",
+        &folder_json("F1", "Reports", "/drive/root:"),
+    );
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F2", "Documents", "/drive/root:"),
+    );
+    service.mock_missing("/me/drive/root:/Documents/2022:");
+    let moved = folder_json("F1", "2022", "/drive/root:/Documents");
+    let patch = service.mock_move("F1", "F2", "rename", &moved);
+
+    service
+        .command()
+        .args(["mv", "id:F1", "/Documents/2022", "--conflict", "rename"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Moved /Reports to /Documents/2022",
+        ));
+    patch.assert();
+    Ok(())
+}
+
+#[test]
+fn cp_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    service.mock_get(
+        "/me/drive/root:/Backup:",
+        &folder_json("F2", "Backup", "/drive/root:"),
+    );
+    let status = json!({ "status": "completed", "percentageComplete": 100.0 });
+    let (copy, monitor) = service.mock_copy("F1", "F2", "Documents", &status);
+
+    service
+        .command()
+        .args(["cp", "/Documents", "/Backup"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("100% complete"))
+        .stdout(predicate::str::contains(
+            "Copied /Documents to /Backup/Documents",
+        ));
+    copy.assert();
+    monitor.assert();
+    Ok(())
+}
+
+#[test]
+fn cp_no_wait() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_get(
+        "/me/drive/root:/a.txt:",
+        &file_json("F1", "a.txt", "/drive/root:", 10),
+    );
+    service.mock_missing("/me/drive/root:/b.txt:");
+    let status = json!({ "status": "inProgress", "percentageComplete": 0.0 });
+    let (copy, monitor) = service.mock_copy("F1", ROOT_ID, "b.txt", &status);
+    let monitor = monitor.expect(0);
+
+    service
+        .command()
+        .args(["cp", "/a.txt", "/b.txt", "--no-wait"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{}/monitor/F1",
+            service.url()
+        )));
+    copy.assert();
+    monitor.assert();
+    Ok(())
+}
+
+#[test]
+fn cp_failure() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_get(
+        "/me/drive/root:/a.txt:",
+        &file_json("F1", "a.txt", "/drive/root:", 10),
+    );
+    service.mock_missing("/me/drive/root:/b.txt:");
+    let status = json!({ "status": "failed", "error": { "code": "nameAlreadyExists" } });
+    service.mock_copy("F1", ROOT_ID, "b.txt", &status);
+
+    service
+        .command()
+        .args(["cp", "/a.txt", "/b.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("nameAlreadyExists"));
+    Ok(())
+}
+
+#[test]
+fn json_output() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_children(
+        ROOT_ID,
+        &[vec![
+            folder_json("F1", "Documents", "/drive/root:"),
+            file_json("F2", "a.txt", "/drive/root:", 10),
+        ]],
+    );
+
+    let result = service
+        .command()
+        .args(["ls", "--output", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    let expected = json!([
+        {
+            "id": "F2",
+            "name": "a.txt",
+            "path": "/a.txt",
+            "type": "file",
+            "size": 10,
+            "modified": "2022-06-18T14:05:14Z",
+            "modifiedBy": null,
+            "webUrl": null
+        },
+        {
+            "id": "F1",
+            "name": "Documents",
+            "path": "/Documents",
+            "type": "folder",
+            "size": 0,
+            "childCount": 0,
+            "modified": "2022-06-18T14:05:14Z",
+            "modifiedBy": null,
+            "webUrl": null
+        }
+    ]);
+    assert_eq!(actual, expected);
+
+    service.mock_drive();
+    let result = service
+        .command()
+        .args(["me", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    assert_eq!(actual["displayName"], "Kevin Phillips");
+    assert_eq!(actual["email"], "kevin@example.com");
+    assert_eq!(actual["drive"]["owner"], "Kevin Phillips");
+    assert_eq!(actual["drive"]["quota"]["remaining"], 4294967296u64);
+    Ok(())
+}
+
+#[test]
+fn ndjson_output() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_get(
+        &format!("/drives/{}/items/{}:/Documents:", DRIVE_ID, ROOT_ID),
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    service.mock_missing(&format!("/drives/{}/items/F1:/Reports:", DRIVE_ID));
+    service.mock_create_folder("F1", "/drive/root:/Documents", "F2", "Reports", "fail");
+    service.mock_missing(&format!("/drives/{}/items/F2:/2022:", DRIVE_ID));
+    service.mock_create_folder("F2", "/drive/root:/Documents/Reports", "F3", "2022", "fail");
+
+    let result = service
+        .command()
+        .args(["mkdir", "-p", "/Documents/Reports/2022", "-o", "ndjson"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(result.get_output().stdout.clone())?;
+    let lines: Vec<Value> = stdout
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["action"], "created");
+    assert_eq!(lines[0]["path"], "/Documents/Reports");
+    assert_eq!(lines[1]["path"], "/Documents/Reports/2022");
+    assert_eq!(lines[1]["item"]["id"], "F3");
+    Ok(())
+}
+
+#[test]
+fn json_errors() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_missing("/me/drive/root:/missing.txt:");
+
+    let result = service
+        .command()
+        .args(["rm", "/missing.txt", "--output", "json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty());
+    let actual: Value = serde_json::from_slice(&result.get_output().stderr)?;
+    assert_eq!(actual["error"]["status"], 404);
+    assert!(actual["error"]["message"].is_string());
+    Ok(())
+}
+
+#[test]
+fn table_output() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_children(
+        ROOT_ID,
+        &[vec![file_json("F2", "a.txt", "/drive/root:", 2048)]],
+    );
+
+    service
+        .command()
+        .args(["ls", "-h", "-o", "table"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "TYPE  SIZE  MODIFIED          MODIFIED BY  ID  NAME\n\
+             -     2.0K  2022-06-18 14:05  -            F2  a.txt\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn df_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_drive();
+
+    service
+        .command()
+        .arg("df")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "DRIVE   TYPE      SIZE  USED  AVAIL  DELETED  USE%  STATE\n\
+             abc123  personal  5.0G  1.0G   4.0G      10M   20%  normal\n",
+        ));
+
+    let result = service
+        .command()
+        .args(["quota", "--bytes", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    assert_eq!(actual["quota"]["used"], 1073741824u64);
+    assert_eq!(actual["quota"]["percentUsed"], 20.0);
+    Ok(())
+}
+
+#[test]
+fn df_warning_threshold() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_drive();
+
+    service
+        .command()
+        .args(["df", "--warn-at", "25"])
+        .assert()
+        .success();
+    service
+        .command()
+        .args(["df", "--warn-at", "20"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("20%"))
+        .stderr(predicate::str::contains("warning threshold of 20%"));
+    service
+        .command()
+        .args(["df", "--warn-at", "120"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("between 0 and 100"));
+    Ok(())
+}
+
+#[test]
+fn du_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    let mut documents = folder_json("F1", "Documents", "/drive/root:");
+    documents["size"] = json!(2048);
+    let mut photos = folder_json("F2", "Photos", "/drive/root:");
+    photos["size"] = json!(1024 * 1024);
+    service.mock_children(
+        ROOT_ID,
+        &[vec![
+            documents,
+            photos,
+            file_json("F3", "a.txt", "/drive/root:", 10),
+        ]],
+    );
+
+    service
+        .command()
+        .args(["du", "-h"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "2.0K\t/Documents\n1.0M\t/Photos\n1.0K\t/\n",
+        ));
+    service
+        .command()
+        .args(["du", "--sort", "size", "-o", "table"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "   SIZE  PATH\n1048576  /Photos\n   2048  /Documents\n   1024  /\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn du_sums_folders_without_sizes() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    let mut documents = folder_json("F1", "Documents", "/drive/root:");
+    documents.as_object_mut().unwrap().remove("size");
+    service.mock_get("/me/drive/root:/Documents:", &documents);
+    let mut reports = folder_json("F2", "Reports", "/drive/root:/Documents");
+    reports.as_object_mut().unwrap().remove("size");
+    service.mock_children(
+        "F1",
+        &[vec![
+            reports,
+            file_json("F3", "b.txt", "/drive/root:/Documents", 100),
+        ]],
+    );
+    service.mock_children(
+        "F2",
+        &[vec![file_json(
+            "F4",
+            "c.txt",
+            "/drive/root:/Documents/Reports",
+            50,
+        )]],
+    );
+
+    let result = service
+        .command()
+        .args(["du", "/Documents", "--depth", "1", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    let expected = json!([
+        { "path": "/Documents/Reports", "size": 50 },
+        { "path": "/Documents", "size": 150 }
+    ]);
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test]
+fn tree_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    let mut folder = folder_json("F1", "Documents", "/drive/root:");
+    folder["folder"]["childCount"] = json!(1);
+    let pages = service.mock_children(
+        ROOT_ID,
+        &[
+            vec![folder, file_json("F2", "b.txt", "/drive/root:", 2048)],
+            vec![file_json("F3", "a.txt", "/drive/root:", 10)],
+        ],
+    );
+    let documents = service.mock_children(
+        "F1",
+        &[vec![file_json("F4", "c.txt", "/drive/root:/Documents", 10)]],
+    );
+
+    service
+        .command()
+        .arg("tree")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/ (3 items)\n\
+             ├── a.txt\n\
+             ├── b.txt\n\
+             └── Documents (1 item)\n\
+             \x20   └── c.txt\n\
+             \n\
+             1 directory, 3 files\n",
+        ));
+    for page in pages {
+        page.assert();
+    }
+    for page in documents {
+        page.assert();
+    }
+
+    service
+        .command()
+        .args(["tree", "-d", "-h"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/ (3 items)\n\
+             └── [       0]  Documents (1 item)\n\
+             \n\
+             1 directory, 0 files\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn tree_depth_limit() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    service.mock_children(
+        ROOT_ID,
+        &[vec![folder_json("F1", "Documents", "/drive/root:")]],
+    );
+
+    let result = service
+        .command()
+        .args(["tree", "-L", "1", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    assert_eq!(actual["directories"], 1);
+    assert_eq!(actual["files"], 0);
+    assert_eq!(actual["tree"]["childCount"], 3);
+    assert_eq!(actual["tree"]["children"][0]["path"], "/Documents");
+    assert!(actual["tree"]["children"][0].get("children").is_none());
+    Ok(())
+}
+
+#[test]
+fn tree_folder_counts() -> TestResult {
+    // Folders beyond the depth limit still show how many items they contain
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    let mut documents = folder_json("F1", "Documents", "/drive/root:");
+    documents["folder"]["childCount"] = json!(12);
+    let mut reports = folder_json("F2", "Reports", "/drive/root:");
+    reports["folder"]["childCount"] = json!(1);
+    service.mock_children(
+        ROOT_ID,
+        &[vec![
+            documents,
+            reports,
+            file_json("F3", "a.txt", "/drive/root:", 10),
+        ]],
+    );
+
+    service
+        .command()
+        .args(["tree", "-L", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/ (3 items)\n\
+             ├── a.txt\n\
+             ├── Documents (12 items)\n\
+             └── Reports (1 item)\n\
+             \n\
+             2 directories, 1 file\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn search_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+    // OneDrive does not always provide the path of the parent folder
+    let mut unlocated = file_json("F3", "report.docx", "", 10);
+    unlocated["parentReference"] = json!({ "driveId": DRIVE_ID, "id": "F1" });
+    let pages = service.mock_search(
+        ROOT_ID,
+        "bob%27%27s%20report",
+        &[
+            vec![
+                file_json("F2", "report.pdf", "/drive/root:/Documents", 2048),
+                folder_json("F4", "Reports", "/drive/root:"),
+            ],
+            vec![unlocated],
+        ],
+    );
+    let parent = service.mock_get(
+        &format!("/drives/{}/items/F1", DRIVE_ID),
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+
+    service
+        .command()
+        .args(["search", "bob's report"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/Documents/report.pdf\n/Reports\n/Documents/report.docx\n",
+        ));
+    for page in pages {
+        page.assert();
+    }
+    parent.assert();
+    Ok(())
+}
+
+#[test]
+fn search_filters() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let mut old = file_json("F3", "old.PDF", "/drive/root:/Documents", 10);
+    old["lastModifiedDateTime"] = json!("2021-01-01T00:00:00Z");
+    service.mock_search(
+        "F1",
+        "report",
+        &[vec![
+            file_json("F2", "report.pdf", "/drive/root:/Documents", 2048),
+            file_json("F4", "report.txt", "/drive/root:/Documents", 10),
+            folder_json("F5", "pdf", "/drive/root:/Documents"),
+            old,
+        ]],
+    );
+
+    service
+        .command()
+        .args(["search", "report", "/Documents", "--ext", ".pdf,docx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/Documents/report.pdf\n/Documents/old.PDF\n",
+        ));
+    service
+        .command()
+        .args(["search", "report", "/Documents", "--type", "folder"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("/Documents/pdf\n"));
+
+    let result = service
+        .command()
+        .args(["search", "report", "/Documents", "-e", "pdf"])
+        .args(["--modified-after", "2022-01-01", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    assert_eq!(actual.as_array().unwrap().len(), 1);
+    assert_eq!(actual[0]["path"], "/Documents/report.pdf");
+    assert_eq!(actual[0]["type"], "file");
+
+    service
+        .command()
+        .args(["search", "report", "/Documents"])
+        .args(["--modified-before", "2022-06-18T14:05:14Z", "-o", "table"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "TYPE  SIZE  MODIFIED          PATH\n\
+             -       10  2021-01-01 00:00  /Documents/old.PDF\n",
+        ));
+    service
+        .command()
+        .args(["search", "report", "--modified-after", "yesterday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid date yesterday"));
+    Ok(())
+}
+
+/// Registers the endpoints describing a small hierarchy of photos for
+/// testing the 'find' subcommand
+///
+/// # Arguments
+///
+/// * `service` - Mock OneDrive service to register the endpoints with
+fn mock_photos(service: &mut MockOneDrive) {
+    service.mock_root();
+    service.mock_get(
+        "/me/drive/root:/Photos:",
+        &folder_json("F1", "Photos", "/drive/root:"),
+    );
+    service.mock_children(
+        ROOT_ID,
+        &[vec![
+            folder_json("F1", "Photos", "/drive/root:"),
+            file_json("F2", "a.heic", "/drive/root:", 10),
+        ]],
+    );
+    let recent = (Utc::now() - Duration::days(2)).to_rfc3339();
+    let mut large = file_json("F3", "IMG_1.heic", "/drive/root:/Photos", 20 * 1024 * 1024);
+    large["lastModifiedDateTime"] = json!(recent);
+    let mut small = file_json("F4", "IMG_2.HEIC", "/drive/root:/Photos", 1024);
+    small["lastModifiedDateTime"] = json!(recent);
+    service.mock_children(
+        "F1",
+        &[
+            vec![large, small],
+            vec![file_json("F5", "notes.txt", "/drive/root:/Photos", 10)],
+        ],
+    );
+}
+
+#[test]
+fn find_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    mock_photos(&mut service);
+
+    service
+        .command()
+        .args(["find", "/Photos", "-name", "*.heic", "-type", "f"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("/Photos/IMG_1.heic\n"));
+    service
+        .command()
+        .args(["find", "-iname", "*.HEIC", "-size", "-2k", "-mtime", "-30"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("/Photos/IMG_2.HEIC\n"));
+    service
+        .command()
+        .args(["find", "/", "--maxdepth", "1", "--type", "d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("/\n/Photos\n"));
+    service
+        .command()
+        .args(["-o", "json", "find", "-mtime", "+30", "-print0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "/a.heic\0/Photos\0/Photos/notes.txt\0",
+        ));
+
+    let result = service
+        .command()
+        .args(["find", "-size", "+10M", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    assert_eq!(actual.as_array().unwrap().len(), 1);
+    assert_eq!(actual[0]["path"], "/Photos/IMG_1.heic");
+    assert_eq!(actual[0]["size"], 20 * 1024 * 1024);
+    Ok(())
+}
+
+#[test]
+fn find_exec() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    mock_photos(&mut service);
+    service.mock_get(
+        "/me/drive/root:/Photos/notes.txt:",
+        &file_json("F5", "notes.txt", "/drive/root:/Photos", 10),
+    );
+    let delete = service.mock_delete("F5");
+
+    service
+        .command()
+        .args([
+            "find", "/Photos", "-name", "*.txt", "-exec", "rm", "-y", "{}", ";",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("Deleted /Photos/notes.txt\n"));
+    delete.assert();
+
+    service
+        .command()
+        .args([
+            "find", "/Photos", "-name", "*.txt", "-exec", "rm", "--bogus", ";",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--bogus"));
+    Ok(())
+}
+
+#[test]
+fn find_exec_options() -> TestResult {
+    let mut service = MockOneDrive::new();
+    // Every subcommand run for the items found shares a single connection
+    let me = service.mock_me().expect(1);
+    mock_photos(&mut service);
+    for (id, name) in [("F3", "IMG_1.heic"), ("F4", "IMG_2.HEIC")] {
+        service.mock_get(
+            &format!("/me/drive/root:/Photos/{}:", name),
+            &file_json(id, name, "/drive/root:/Photos", 10),
+        );
+    }
+
+    let result = service
+        .command()
+        .args([
+            "find", "/Photos", "-iname", "*.heic", "-exec", "stat", "-o", "ndjson", "{}", ";",
+        ])
+        .assert()
+        .success();
+    me.assert();
+    let stdout = String::from_utf8(result.get_output().stdout.clone())?;
+    let paths: Vec<Value> = stdout
+        .lines()
+        .map(serde_json::from_str::<Value>)
+        .map(|r| r.map(|v| v["path"].clone()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(paths, vec!["/Photos/IMG_1.heic", "/Photos/IMG_2.HEIC"]);
+    Ok(())
+}
+
+#[test]
+fn stat_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    let mut file = file_json("F2", "Budget.xlsx", "/drive/root:/Documents", 35212);
+    file["eTag"] = json!("\"{F2},2\"");
+    file["cTag"] = json!("\"c:{F2},2\"");
+    file["file"]["hashes"] = json!({ "quickXorHash": "wHBc=", "sha1Hash": "ABCD" });
+    file["createdDateTime"] = json!("2022-06-01T08:00:00Z");
+    file["createdBy"] = json!({ "user": { "displayName": "Kevin Phillips" } });
+    file["lastModifiedBy"] = json!({ "application": { "displayName": "Excel" } });
+    file["parentReference"]["id"] = json!("F1");
+    file["shared"] = json!({
+        "scope": "users",
+        "sharedBy": { "user": { "displayName": "Kevin Phillips" } },
+        "sharedDateTime": "2022-06-20T09:30:00Z"
+    });
+    file["webUrl"] = json!("https://onedrive.live.com/F2");
+    service.mock_get("/me/drive/root:/Documents/Budget.xlsx:", &file);
+    service.mock_get("/me/drive/items/F2", &file);
+
+    service
+        .command()
+        .args(["stat", "/Documents/Budget.xlsx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "Name:          Budget.xlsx\n\
+             Path:          /Documents/Budget.xlsx\n\
+             Type:          file\n\
+             ID:            F2\n\
+             Size:          35212 (34K)\n\
+             ETag:          \"{F2},2\"\n\
+             CTag:          \"c:{F2},2\"\n\
+             MIME type:     text/plain\n\
+             QuickXorHash:  wHBc=\n\
+             SHA1:          ABCD\n\
+             Created:       2022-06-01 08:00:00 UTC by Kevin Phillips\n\
+             Modified:      2022-06-18 14:05:14 UTC by Excel\n\
+             Parent:        /Documents (F1)\n\
+             Shared:        with users by Kevin Phillips on 2022-06-20 09:30:00 UTC\n\
+             Web URL:       https://onedrive.live.com/F2\n",
+        ));
+
+    let result = service
+        .command()
+        .args(["stat", "id:F2", "-o", "json"])
+        .assert()
+        .success();
+    let actual: Value = serde_json::from_slice(&result.get_output().stdout)?;
+    assert_eq!(actual["path"], "/Documents/Budget.xlsx");
+    assert_eq!(actual["eTag"], "\"{F2},2\"");
+    assert_eq!(actual["hashes"]["sha1Hash"], "ABCD");
+    assert_eq!(actual["hashes"]["sha256Hash"], Value::Null);
+    assert_eq!(actual["createdBy"], "Kevin Phillips");
+    assert_eq!(actual["parentPath"], "/Documents");
+    assert_eq!(actual["shared"]["scope"], "users");
+    assert_eq!(actual["shared"]["shared"], "2022-06-20T09:30:00Z");
+    Ok(())
+}
+
+#[test]
+fn stat_folder() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_root();
+
+    service
+        .command()
+        .args(["stat", "/"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "Name:          root\n\
+             Path:          /\n\
+             Type:          folder\n\
+             ID:            ABC123!101\n\
+             Size:          1024 (1.0K)\n\
+             Items:         3\n\
+             ETag:          -\n\
+             CTag:          -\n\
+             Created:       -\n\
+             Modified:      -\n\
+             Shared:        no\n\
+             Web URL:       -\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn cat_command() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/logs/app.log:",
+        &file_json("F1", "app.log", "/drive/root:/logs", 12),
+    );
+    service.mock_get(
+        "/me/drive/items/F2",
+        &file_json("F2", "b.log", "/drive/root:/logs", 6),
+    );
+    service.mock_download("F1", None, "INFO\nERROR\n");
+    service.mock_download("F2", None, "DEBUG\n");
+
+    service
+        .command()
+        .args(["cat", "/logs/app.log", "id:F2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("INFO\nERROR\nDEBUG\n"));
+    Ok(())
+}
+
+#[test]
+fn cat_range() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/logs/app.log:",
+        &file_json("F1", "app.log", "/drive/root:/logs", 12),
+    );
+    let (request, download) = service.mock_download("F1", Some("bytes=-6"), "ERROR\n");
+
+    service
+        .command()
+        .args(["cat", "/logs/app.log", "--range", "-6"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("ERROR\n"));
+    request.assert();
+    download.assert();
+
+    service
+        .command()
+        .args(["cat", "/logs/app.log", "-r", "10-5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid range 10-5"));
+    Ok(())
+}
+
+#[test]
+fn cat_folder() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/logs:",
+        &folder_json("F1", "logs", "/drive/root:"),
+    );
+
+    service
+        .command()
+        .args(["cat", "/logs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/logs is not a file"));
+    Ok(())
+}
+
+#[test]
+fn upload_from_stdin() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let content = "hello world";
+    service.mock_me();
+    service.mock_missing("/me/drive/root:/backups/x.tgz:");
+    service.mock_get(
+        "/me/drive/root:/backups:",
+        &folder_json("F1", "backups", "/drive/root:"),
+    );
+    let (session, upload) = service.mock_upload_session("F1", "x.tgz", content);
+
+    service
+        .command()
+        .args(["upload", "-", "/backups/x.tgz"])
+        .write_stdin(content)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully uploaded x.tgz"));
+    session.assert();
+    upload.assert();
+
+    // Data read from stdin has no name, so it can't be placed in a folder
+    service
+        .command()
+        .args(["upload", "-", "/backups"])
+        .write_stdin(content)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "/backups is a folder, so a name is needed for the new file",
+        ));
+    Ok(())
+}
+
+#[test]
+fn upload_to_folder() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let content = "hello world";
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("hello.txt");
+    write(&source, content)?;
+
+    service.mock_me();
+    service.mock_get(
+        "/me/drive/root:/backups:",
+        &folder_json("F1", "backups", "/drive/root:"),
+    );
+    let (session, upload) = service.mock_upload_session("F1", "hello.txt", content);
+
+    service
+        .command()
+        .arg("upload")
+        .arg(&source)
+        .arg("/backups")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully uploaded hello.txt"));
+    session.assert();
+    upload.assert();
+    Ok(())
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::*;
+use mockito::Matcher;
+use onedrive_manager::api::blocking::OneDrive;
+use onedrive_manager::api::OneDrive as AsyncOneDrive;
+use serde_json::json;
+use std::error::Error;
+use std::fs::{read_dir, read_to_string, write};
+use tempfile::tempdir;
+
+type TestResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+/// Constructs a blocking client connected to a mock service
+fn client(service: &MockOneDrive) -> OneDrive {
+    let inner = AsyncOneDrive::builder(ACCESS_TOKEN)
+        .base_url(&service.url())
+        .build();
+    OneDrive::from_async(inner).unwrap()
+}
+
+#[test]
+fn me() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_me();
+
+    let me = client(&service).me()?;
+    assert_eq!(me.display_name(), "Kevin Phillips");
+    Ok(())
+}
+
+#[test]
+fn list() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_root();
+    service.mock_children(
+        ROOT_ID,
+        &[
+            vec![folder_json("F1", "Documents", "/drive/root:")],
+            vec![file_json("F2", "a.txt", "/drive/root:", 1)],
+        ],
+    );
+
+    let items = client(&service).list("/")?;
+    let names: Vec<&str> = items.iter().map(|i| i.name()).collect();
+    assert_eq!(names, vec!["Documents", "a.txt"]);
+    Ok(())
+}
+
+#[test]
+fn upload_and_download() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let content = "hello world";
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("hello.txt");
+    write(&source, content)?;
+
+    service.mock_root();
+    let (session, upload) = service.mock_upload_session(ROOT_ID, "hello.txt", content);
+    service.mock_get(
+        "/me/drive/root:/hello.txt:",
+        &file_json("NEW1", "hello.txt", "/drive/root:", 11),
+    );
+    service
+        .server
+        .mock(
+            "GET",
+            format!("/drives/{}/items/NEW1/content", DRIVE_ID).as_str(),
+        )
+        .with_body(content)
+        .create();
+
+    let client = client(&service);
+    let item = client.upload(&source, "/")?;
+    assert_eq!(item.name(), "hello.txt");
+    session.assert();
+    upload.assert();
+
+    let destination = temp_dir.path().join("copy.txt");
+    assert_eq!(client.download("/hello.txt", &destination)?, 11);
+    assert_eq!(read_to_string(destination)?, content);
+    Ok(())
+}
+
+#[test]
+fn failed_download_keeps_destination() -> TestResult {
+    let mut service = MockOneDrive::new();
+    let temp_dir = tempdir()?;
+    let destination = temp_dir.path().join("hello.txt");
+    write(&destination, "original")?;
+
+    service.mock_missing("/me/drive/root:/missing.txt:");
+    service.mock_get(
+        "/me/drive/root:/hello.txt:",
+        &file_json("NEW1", "hello.txt", "/drive/root:", 11),
+    );
+    service
+        .server
+        .mock(
+            "GET",
+            format!("/drives/{}/items/NEW1/content", DRIVE_ID).as_str(),
+        )
+        .with_status(500)
+        .create();
+
+    let client = client(&service);
+    assert!(client.download("/missing.txt", &destination).is_err());
+    assert_eq!(read_to_string(&destination)?, "original");
+    assert!(client.download("/hello.txt", &destination).is_err());
+    assert_eq!(read_to_string(&destination)?, "original");
+    assert_eq!(read_dir(temp_dir.path())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn mkdir_and_delete() -> TestResult {
+    let mut service = MockOneDrive::new();
+    service.mock_get(
+        "/me/drive/root:/Documents:",
+        &folder_json("F1", "Documents", "/drive/root:"),
+    );
+    let create = service
+        .server
+        .mock(
+            "POST",
+            format!("/drives/{}/items/F1/children", DRIVE_ID).as_str(),
+        )
+        .match_body(Matcher::PartialJson(json!({
+            "name": "Reports",
+            "folder": {},
+            "@microsoft.graph.conflictBehavior": "fail"
+        })))
+        .with_status(201)
+        .with_body(folder_json("F2", "Reports", "/drive/root:/Documents").to_string())
+        .create();
+    service.mock_get(
+        "/me/drive/root:/Documents/Reports:",
+        &folder_json("F2", "Reports", "/drive/root:/Documents"),
+    );
+    let delete = service
+        .server
+        .mock("DELETE", format!("/drives/{}/items/F2", DRIVE_ID).as_str())
+        .with_status(204)
+        .create();
+
+    let client = client(&service);
+    let folder = client.mkdir("/Documents/Reports")?;
+    assert_eq!(folder.id(), "F2");
+    create.assert();
+
+    client.delete("/Documents/Reports")?;
+    delete.assert();
+    Ok(())
+}
@@ -0,0 +1,534 @@
+//! Mock implementation of the OneDrive REST API and OAuth services, allowing
+//! the command line interface to be exercised without a Microsoft account
+// Each test suite only uses a subset of these helpers
+#![allow(dead_code)]
+use assert_cmd::Command;
+use mockito::{Matcher, Mock, ServerGuard};
+use serde_json::{json, Value};
+use std::fs::{create_dir, read_to_string, write};
+use std::path::PathBuf;
+use tempfile::{tempdir, TempDir};
+
+pub const APP_NAME: &str = "onedrive_manager";
+/// Identifier of the drive owned by our mock user
+pub const DRIVE_ID: &str = "abc123";
+/// Identifier of the root folder of our mock drive
+pub const ROOT_ID: &str = "ABC123!101";
+/// Authentication token accepted by the mock REST API
+pub const ACCESS_TOKEN: &str = "valid-access-token";
+/// Refresh token stored in the app configuration by default
+pub const REFRESH_TOKEN: &str = "valid-refresh-token";
+
+/// Mock OneDrive service paired with an isolated home folder containing
+/// the app configuration file, so tests never touch real user data
+pub struct MockOneDrive {
+    pub server: ServerGuard,
+    home: TempDir,
+}
+
+impl MockOneDrive {
+    /// Creates a new mock service with an app configuration file pointing to it
+    pub fn new() -> Self {
+        let retval = MockOneDrive {
+            server: mockito::Server::new(),
+            home: tempdir().unwrap(),
+        };
+        retval.write_config(ACCESS_TOKEN, true);
+        retval
+    }
+
+    /// Base URL of the mock service
+    pub fn url(&self) -> String {
+        self.server.url()
+    }
+
+    /// Path to the app configuration file used by the tests
+    pub fn config_file(&self) -> PathBuf {
+        self.home
+            .path()
+            .join(".onedrive_manager")
+            .join("config.yml")
+    }
+
+    /// Generates a new app configuration file
+    ///
+    /// # Arguments
+    ///
+    /// * `auth_token` - Authentication token to store in the file
+    /// * `with_urls` - True to point the app to the mock service through the
+    ///                 configuration file, false to use the default URLs
+    pub fn write_config(&self, auth_token: &str, with_urls: bool) {
+        let mut data = format!(
+            "auth_token: {}\nrefresh_token: {}\n",
+            auth_token, REFRESH_TOKEN
+        );
+        if with_urls {
+            data.push_str(&format!("api_url: {0}\nauth_url: {0}\n", self.url()));
+        }
+        let folder = self.config_file().parent().unwrap().to_path_buf();
+        if !folder.is_dir() {
+            create_dir(folder).unwrap();
+        }
+        write(self.config_file(), data).unwrap();
+    }
+
+    /// Contents of the app configuration file
+    pub fn config(&self) -> String {
+        read_to_string(self.config_file()).unwrap()
+    }
+
+    /// Prepares a command to run our app against the mock service
+    pub fn command(&self) -> Command {
+        let mut retval = Command::cargo_bin(APP_NAME).unwrap();
+        retval
+            .env("HOME", self.home.path())
+            .env_remove("ONEDRIVE_API_URL")
+            .env_remove("ONEDRIVE_AUTH_URL");
+        retval
+    }
+
+    /// Registers an endpoint that only succeeds for authenticated requests
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the endpoint, relative to the root of the service
+    /// * `body` - JSON data returned by the endpoint
+    pub fn mock_get(&mut self, path: &str, body: &Value) -> Mock {
+        self.server
+            .mock("GET", path)
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .with_header("content-type", "application/json")
+            .with_body(body.to_string())
+            .create()
+    }
+
+    /// Registers an endpoint reporting that the requested item does not exist
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the endpoint, relative to the root of the service
+    pub fn mock_missing(&mut self, path: &str) -> Mock {
+        self.server
+            .mock("GET", path)
+            .with_status(404)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"error": {"code": "itemNotFound", "message": "Item does not exist"}}"#)
+            .create()
+    }
+
+    /// Registers the endpoint that creates a new folder
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - Identifier of the folder the new folder is created in
+    /// * `parent_path` - Path of the parent folder, ie: "/drive/root:/Documents"
+    /// * `id` - Identifier assigned to the new folder
+    /// * `name` - Name of the new folder
+    /// * `conflict` - Expected conflict behavior for the request
+    pub fn mock_create_folder(
+        &mut self,
+        parent_id: &str,
+        parent_path: &str,
+        id: &str,
+        name: &str,
+        conflict: &str,
+    ) -> Mock {
+        self.server
+            .mock(
+                "POST",
+                format!("/drives/{}/items/{}/children", DRIVE_ID, parent_id).as_str(),
+            )
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .match_body(Matcher::PartialJson(json!({
+                "name": name,
+                "folder": {},
+                "@microsoft.graph.conflictBehavior": conflict
+            })))
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(folder_json(id, name, parent_path).to_string())
+            .create()
+    }
+
+    /// Registers the endpoint that moves and renames an item
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of the item being moved
+    /// * `parent_id` - Identifier of the folder the item is moved to
+    /// * `conflict` - Expected conflict behavior for the request
+    /// * `moved` - Metadata describing the item after it has been moved
+    pub fn mock_move(&mut self, id: &str, parent_id: &str, conflict: &str, moved: &Value) -> Mock {
+        self.server
+            .mock(
+                "PATCH",
+                format!("/drives/{}/items/{}", DRIVE_ID, id).as_str(),
+            )
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .match_query(Matcher::UrlEncoded(
+                "@microsoft.graph.conflictBehavior".into(),
+                conflict.into(),
+            ))
+            .match_body(Matcher::Json(json!({
+                "parentReference": { "id": parent_id },
+                "name": moved["name"],
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(moved.to_string())
+            .create()
+    }
+
+    /// Registers the endpoints needed to copy an item, along with the monitor
+    /// reporting the progress of the copy
+    /// Returns the mocks for starting the copy and checking its progress
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of the item being copied
+    /// * `parent_id` - Identifier of the folder the item is copied to
+    /// * `name` - Name of the new copy
+    /// * `status` - Progress reported by the monitor
+    pub fn mock_copy(
+        &mut self,
+        id: &str,
+        parent_id: &str,
+        name: &str,
+        status: &Value,
+    ) -> (Mock, Mock) {
+        let monitor_url = format!("{}/monitor/{}", self.url(), id);
+        let copy = self
+            .server
+            .mock(
+                "POST",
+                format!("/drives/{}/items/{}/copy", DRIVE_ID, id).as_str(),
+            )
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .match_query(Matcher::Any)
+            .match_body(Matcher::Json(json!({
+                "parentReference": { "driveId": DRIVE_ID, "id": parent_id },
+                "name": name,
+            })))
+            .with_status(202)
+            .with_header("location", &monitor_url)
+            .create();
+        let monitor = self
+            .server
+            .mock("GET", format!("/monitor/{}", id).as_str())
+            .match_header("authorization", Matcher::Missing)
+            .with_status(202)
+            .with_header("content-type", "application/json")
+            .with_body(status.to_string())
+            .create();
+        (copy, monitor)
+    }
+
+    /// Registers the endpoints that download the content of a file. Like
+    /// OneDrive, requests for the content are redirected to a separate,
+    /// pre-authenticated download service
+    /// Returns the mocks for requesting the content and downloading it
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of the file being downloaded
+    /// * `range` - Value of the Range header expected by both services, if any
+    /// * `content` - Content returned by the download service
+    pub fn mock_download(&mut self, id: &str, range: Option<&str>, content: &str) -> (Mock, Mock) {
+        let range = match range {
+            Some(r) => Matcher::Exact(r.to_string()),
+            None => Matcher::Missing,
+        };
+        let request = self
+            .server
+            .mock(
+                "GET",
+                format!("/drives/{}/items/{}/content", DRIVE_ID, id).as_str(),
+            )
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .match_header("range", range.clone())
+            .with_status(302)
+            .with_header("location", &format!("{}/download/{}", self.url(), id))
+            .create();
+        let download = self
+            .server
+            .mock("GET", format!("/download/{}", id).as_str())
+            .match_header("authorization", Matcher::Missing)
+            .match_header("range", range)
+            .with_body(content)
+            .create();
+        (request, download)
+    }
+
+    /// Registers the endpoint that moves an item to the recycle bin
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of the item being deleted
+    pub fn mock_delete(&mut self, id: &str) -> Mock {
+        self.server
+            .mock(
+                "DELETE",
+                format!("/drives/{}/items/{}", DRIVE_ID, id).as_str(),
+            )
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .with_status(204)
+            .create()
+    }
+
+    /// Registers the endpoint that deletes an item without using the recycle bin
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of the item being deleted
+    pub fn mock_permanent_delete(&mut self, id: &str) -> Mock {
+        let path = format!("/drives/{}/items/{}/permanentDelete", DRIVE_ID, id);
+        self.server
+            .mock("POST", path.as_str())
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .with_status(204)
+            .create()
+    }
+
+    /// Registers the endpoint describing the currently logged in user
+    pub fn mock_me(&mut self) -> Mock {
+        self.mock_get("/me", &user_json()).expect_at_least(1)
+    }
+
+    /// Registers the endpoint describing the default drive of the user
+    pub fn mock_drive(&mut self) -> Mock {
+        self.mock_get("/me/drive", &drive_json())
+    }
+
+    /// Registers the endpoint describing the root folder of the drive
+    pub fn mock_root(&mut self) -> Mock {
+        self.mock_get("/me/drive/root", &root_json())
+    }
+
+    /// Registers the endpoints producing the contents of a folder, split
+    /// into several pages of results linked together with "@odata.nextLink"
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_id` - Identifier of the folder being listed
+    /// * `pages` - Items returned in each page of results
+    pub fn mock_children(&mut self, folder_id: &str, pages: &[Vec<Value>]) -> Vec<Mock> {
+        let first_page = format!("/drives/{}/items/{}/children", DRIVE_ID, folder_id);
+        self.mock_pages(&first_page, pages)
+    }
+
+    /// Registers the endpoints producing the results of a search within a
+    /// folder, split into several pages of results
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_id` - Identifier of the folder being searched
+    /// * `query` - Search query, as encoded in the request URL
+    /// * `pages` - Items returned in each page of results
+    pub fn mock_search(&mut self, folder_id: &str, query: &str, pages: &[Vec<Value>]) -> Vec<Mock> {
+        let first_page = format!(
+            "/drives/{}/items/{}/search(q='{}')",
+            DRIVE_ID, folder_id, query
+        );
+        self.mock_pages(&first_page, pages)
+    }
+
+    /// Registers the endpoints producing a collection of items, split into
+    /// several pages of results linked together with "@odata.nextLink"
+    ///
+    /// # Arguments
+    ///
+    /// * `first_page` - Path of the endpoint producing the first page
+    /// * `pages` - Items returned in each page of results
+    fn mock_pages(&mut self, first_page: &str, pages: &[Vec<Value>]) -> Vec<Mock> {
+        let mut retval = Vec::new();
+        for (index, items) in pages.iter().enumerate() {
+            let path = match index {
+                0 => first_page.to_string(),
+                _ => format!("{}/page{}", first_page, index),
+            };
+            let mut body = json!({ "value": items });
+            if index + 1 < pages.len() {
+                body["@odata.nextLink"] =
+                    json!(format!("{}{}/page{}", self.url(), first_page, index + 1));
+            }
+            retval.push(self.mock_get(&path, &body));
+        }
+        retval
+    }
+
+    /// Registers the endpoints needed to upload a file using an upload session
+    /// Returns the mocks for creating the session and transferring the content
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_id` - Identifier of the folder receiving the file
+    /// * `name` - Name of the file being uploaded
+    /// * `content` - Expected content of the file
+    pub fn mock_upload_session(
+        &mut self,
+        folder_id: &str,
+        name: &str,
+        content: &str,
+    ) -> (Mock, Mock) {
+        let session = self
+            .server
+            .mock(
+                "POST",
+                format!(
+                    "/drives/{}/items/{}:/{}:/createUploadSession",
+                    DRIVE_ID, folder_id, name
+                )
+                .as_str(),
+            )
+            .match_header("authorization", format!("Bearer {}", ACCESS_TOKEN).as_str())
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "uploadUrl": format!("{}/upload/session1", self.url()),
+                    "expirationDateTime": "2030-01-01T00:00:00Z",
+                    "nextExpectedRanges": ["0-"]
+                })
+                .to_string(),
+            )
+            .create();
+        let upload = self
+            .server
+            .mock("PUT", "/upload/session1")
+            .match_header(
+                "content-range",
+                format!("bytes 0-{}/{}", content.len() - 1, content.len()).as_str(),
+            )
+            .match_body(content)
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(file_json("NEW1", name, "/drive/root:", content.len() as u64).to_string())
+            .create();
+        (session, upload)
+    }
+
+    /// Registers the endpoint that renews authentication tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `access_token` - New authentication token issued by the service
+    /// * `refresh_token` - New refresh token issued by the service
+    pub fn mock_token_refresh(&mut self, access_token: &str, refresh_token: &str) -> Mock {
+        self.server
+            .mock("POST", "/oauth20_token.srf")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+                Matcher::UrlEncoded("refresh_token".into(), REFRESH_TOKEN.into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "token_type": "bearer",
+                    "expires_in": 3600,
+                    "scope": "onedrive.readwrite",
+                    "access_token": access_token,
+                    "refresh_token": refresh_token,
+                    "user_id": "741a798783ae5a91"
+                })
+                .to_string(),
+            )
+            .create()
+    }
+
+    /// Registers an endpoint that rejects an authentication token as expired
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the endpoint, relative to the root of the service
+    /// * `token` - Authentication token to reject
+    pub fn mock_expired_token(&mut self, path: &str, token: &str) -> Mock {
+        self.server
+            .mock("GET", path)
+            .match_header("authorization", format!("Bearer {}", token).as_str())
+            .with_status(401)
+            .create()
+    }
+}
+
+/// Profile data for our mock user
+pub fn user_json() -> Value {
+    json!({
+        "@odata.context": "https://graph.microsoft.com/v1.0/$metadata#users/$entity",
+        "businessPhones": [],
+        "displayName": "Kevin Phillips",
+        "givenName": "Kevin",
+        "id": "741a798783ae5a91",
+        "jobTitle": null,
+        "mail": "kevin@example.com",
+        "mobilePhone": null,
+        "officeLocation": null,
+        "preferredLanguage": "en-US",
+        "surname": "Phillips",
+        "userPrincipalName": "kevin@example.com"
+    })
+}
+
+/// Metadata for the default drive of our mock user
+pub fn drive_json() -> Value {
+    json!({
+        "id": DRIVE_ID,
+        "driveType": "personal",
+        "name": "OneDrive",
+        "webUrl": "https://onedrive.live.com/?cid=abc123",
+        "owner": { "user": { "id": "741a798783ae5a91", "displayName": "Kevin Phillips" } },
+        "quota": {
+            "total": 5368709120u64,
+            "used": 1073741824u64,
+            "remaining": 4294967296u64,
+            "deleted": 10485760,
+            "state": "normal"
+        }
+    })
+}
+
+/// Metadata for the root folder of our mock drive
+pub fn root_json() -> Value {
+    json!({
+        "id": ROOT_ID,
+        "name": "root",
+        "root": {},
+        "size": 1024,
+        "folder": { "childCount": 3 },
+        "parentReference": { "driveId": DRIVE_ID, "driveType": "personal" }
+    })
+}
+
+/// Metadata for a folder stored in our mock drive
+///
+/// # Arguments
+///
+/// * `id` - Unique identifier of the folder
+/// * `name` - Name of the folder
+/// * `parent_path` - Path of the parent folder, ie: "/drive/root:/Documents"
+pub fn folder_json(id: &str, name: &str, parent_path: &str) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "size": 0,
+        "folder": { "childCount": 0 },
+        "lastModifiedDateTime": "2022-06-18T14:05:14Z",
+        "parentReference": { "driveId": DRIVE_ID, "driveType": "personal", "path": parent_path }
+    })
+}
+
+/// Metadata for a file stored in our mock drive
+///
+/// # Arguments
+///
+/// * `id` - Unique identifier of the file
+/// * `name` - Name of the file
+/// * `parent_path` - Path of the parent folder, ie: "/drive/root:/Documents"
+/// * `size` - Size of the file in bytes
+pub fn file_json(id: &str, name: &str, parent_path: &str, size: u64) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "size": size,
+        "file": { "mimeType": "text/plain", "hashes": { "sha1Hash": "ABCD" } },
+        "lastModifiedDateTime": "2022-06-18T14:05:14Z",
+        "parentReference": { "driveId": DRIVE_ID, "driveType": "personal", "path": parent_path }
+    })
+}